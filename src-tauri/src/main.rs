@@ -1,14 +1,24 @@
 ﻿// Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::process::{Child, Stdio};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use base64::Engine;
 use msi_extract::MsiExtractor;
 use reqwest::blocking::Client;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tauri::Manager;
 
 #[cfg(feature = "system-tray")]
@@ -27,45 +37,623 @@ struct HubPreferences {
   install_dir: Option<String>,
   #[serde(default, skip_serializing_if = "Option::is_none")]
   installer_type: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  custom_ca_cert_path: Option<String>,
+  #[serde(default)]
+  danger_accept_invalid_certs: bool,
+  #[serde(default)]
+  scan_downloads: bool,
+  #[serde(default)]
+  defer_while_running: bool,
+  /// Hosts downloads may come from; each entry also allows its subdomains.
+  /// Empty means any host.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  download_host_allowlist: Vec<String>,
+  /// Turned on through `request_notification_permission`; until then
+  /// notifications are delivered as in-app `hub-notification` events.
+  #[serde(default)]
+  notifications_enabled: bool,
+  /// Webview zoom chosen with `set_window_scale`; unset means it follows the
+  /// primary monitor.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  window_scale: Option<f64>,
+  /// Browser-style zoom on top of the UI scale, changed with Ctrl+/-.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  zoom_level: Option<f64>,
+  /// How many installs may extract at once; unset means one. Downloads are
+  /// not limited by this.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  max_concurrent_installs: Option<u32>,
+  /// Redirects a request may follow; unset means `DEFAULT_MAX_REDIRECTS`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  max_redirects: Option<u32>,
+  /// One of `KNOWN_THEMES`, set through `set_theme`; unset follows the
+  /// system like "system" does.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  theme: Option<String>,
+  /// Opt-in anonymous failure reports; see `FailureReport` for what is sent.
+  #[serde(default)]
+  telemetry_enabled: bool,
+  /// Where failure reports are posted. Without one they stay queued.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  telemetry_endpoint: Option<String>,
 }
 
 struct AppState {
   prefs: Mutex<HubPreferences>,
+  exits: Mutex<HashMap<String, AppExitInfo>>,
+  active_downloads: Mutex<HashSet<PathBuf>>,
+  pending_deep_link: Mutex<Option<DeepLink>>,
+  download_queue: Mutex<Vec<QueuedDownload>>,
+  download_worker_running: Mutex<bool>,
+  /// Ids of apps started through `launch_app` that have not exited yet; an id
+  /// appears once per running instance.
+  running_apps: Mutex<Vec<String>>,
+  /// Ids of apps whose paused install the user cancelled through
+  /// `cancel_deferred_work`; taken by the waiting install.
+  cancelled_deferrals: Mutex<HashSet<String>>,
+  /// Shared by every download and manifest request so connections are pooled;
+  /// built on first use and dropped when TLS preferences change.
+  http_client: Mutex<Option<Client>>,
+  /// Set when startup had to recover or reset the preferences file; reported
+  /// to the main window once.
+  preferences_recovery: Mutex<Option<PreferencesRecovery>>,
+  install_slots: InstallSlots,
+  /// Whether a tray icon can actually be shown. Without one, closing and
+  /// minimizing never hide the window, since there would be no way back.
+  tray_available: bool,
 }
 
-fn hub_preferences_path() -> Option<PathBuf> {
+fn hub_data_dir() -> Option<PathBuf> {
   let base = tauri::api::path::local_data_dir()?;
-  Some(base.join("EnderFall").join("Hub").join("preferences.json"))
+  Some(base.join("EnderFall").join("Hub"))
 }
 
-fn load_hub_preferences() -> HubPreferences {
+fn hub_preferences_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("preferences.json"))
+}
+
+fn installer_cache_dir() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("Installers"))
+}
+
+fn hub_cache_dir() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("Cache"))
+}
+
+/// Once the log grows past this it is moved to `hub.log.1`, replacing the
+/// previous one.
+const LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+fn hub_log_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("hub.log"))
+}
+
+/// Appends a line to `hub.log`. Release builds have no console, so this is
+/// where warnings and command-line errors go; debug builds also print them.
+fn write_log(level: &str, message: &str) {
+  let line = format!("{} {}: {}\n", unix_timestamp(), level, message);
+  if cfg!(debug_assertions) {
+    eprint!("{}", line);
+  }
+  let Some(path) = hub_log_path() else {
+    return;
+  };
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if std::fs::metadata(&path).is_ok_and(|meta| meta.len() > LOG_MAX_BYTES) {
+    let _ = std::fs::rename(&path, path.with_extension("log.1"));
+  }
+  if let Ok(mut file) = std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+  {
+    let _ = file.write_all(line.as_bytes());
+  }
+}
+
+/// Turns an app id or version into something safe to use as a file name.
+fn cache_key(value: &str) -> String {
+  value
+    .chars()
+    .map(|c| {
+      if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .collect()
+}
+
+/// Reads the preferences file. A missing file just means defaults; any other
+/// failure (an unreadable file or an inaccessible directory) is returned as a
+/// warning alongside the defaults.
+fn read_hub_preferences() -> (HubPreferences, Option<String>) {
   let prefs_path = match hub_preferences_path() {
     Some(path) => path,
-    None => return HubPreferences::default(),
+    None => return (HubPreferences::default(), None),
   };
   let data = match std::fs::read(&prefs_path) {
     Ok(data) => data,
-    Err(_) => return HubPreferences::default(),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+      let dir_error = prefs_path
+        .parent()
+        .and_then(|dir| std::fs::read_dir(dir).err().map(|err| (dir, err)))
+        .filter(|(_, err)| err.kind() != std::io::ErrorKind::NotFound);
+      let warning = dir_error.map(|(dir, err)| {
+        format!(
+          "Cannot read settings: {} is not accessible ({}).",
+          dir.display(),
+          err
+        )
+      });
+      return (HubPreferences::default(), warning);
+    }
+    Err(err) => {
+      let warning = format!(
+        "Cannot read settings from {} ({}).",
+        prefs_path.display(),
+        err
+      );
+      return (HubPreferences::default(), Some(warning));
+    }
+  };
+  (serde_json::from_slice(&data).unwrap_or_default(), None)
+}
+
+fn load_hub_preferences() -> HubPreferences {
+  read_hub_preferences().0
+}
+
+/// Confirms a file can be created in `dir` before anything is written there,
+/// so a read-only profile fails with a message that names the folder.
+fn ensure_dir_writable(dir: &Path) -> Result<(), String> {
+  let not_writable = |err: std::io::Error| {
+    format!(
+      "Cannot save settings: {} is not writable ({}).",
+      dir.display(),
+      err
+    )
   };
-  serde_json::from_slice(&data).unwrap_or_default()
+  std::fs::create_dir_all(dir).map_err(not_writable)?;
+  let probe = dir.join(format!(".write-test-{}", std::process::id()));
+  std::fs::write(&probe, b"").map_err(not_writable)?;
+  let _ = std::fs::remove_file(probe);
+  Ok(())
+}
+
+/// Returns a warning when the preferences file or its folder cannot be read or
+/// written, or `None` when settings will load and save normally.
+#[tauri::command]
+fn check_preferences_access() -> Option<String> {
+  if let (_, Some(warning)) = read_hub_preferences() {
+    return Some(warning);
+  }
+  let dir = hub_preferences_path()?.parent()?.to_path_buf();
+  ensure_dir_writable(&dir).err()
 }
 
+/// Writes to a temp file and renames it over the preferences, so a crash
+/// mid-write never leaves a truncated file behind. The previous file, if it
+/// still parses, is kept as a `.bak` for `recover_hub_preferences`.
 fn write_hub_preferences(prefs: &HubPreferences) -> Result<(), String> {
   let prefs_path = hub_preferences_path().ok_or("Missing local data dir")?;
   if let Some(parent) = prefs_path.parent() {
     std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
   }
   let data = serde_json::to_vec_pretty(prefs).map_err(|e| e.to_string())?;
-  std::fs::write(prefs_path, data).map_err(|e| e.to_string())?;
+  let temp_path = prefs_path.with_extension("json.tmp");
+  std::fs::write(&temp_path, data).map_err(|e| e.to_string())?;
+  if parse_preferences_file(&prefs_path).is_some() {
+    let _ = std::fs::copy(&prefs_path, prefs_path.with_extension("json.bak"));
+  }
+  std::fs::rename(&temp_path, &prefs_path).map_err(|e| e.to_string())?;
   Ok(())
 }
 
+fn parse_preferences_file(path: &Path) -> Option<HubPreferences> {
+  serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PreferencesRecovery {
+  /// The backup or temp file the settings were restored from; `None` when
+  /// nothing usable was found and defaults were used.
+  source: Option<String>,
+  /// Where the unreadable preferences file was moved for inspection.
+  preserved: Option<String>,
+}
+
+/// Runs once at startup. When the preferences file exists but does not parse,
+/// restores it from the `.bak`, then from a leftover `.tmp`, and otherwise sets
+/// it aside so defaults load. Returns what happened, or `None` when the file
+/// was fine or absent.
+fn recover_hub_preferences() -> Option<PreferencesRecovery> {
+  let prefs_path = hub_preferences_path()?;
+  let temp_path = prefs_path.with_extension("json.tmp");
+  if !prefs_path.exists() || parse_preferences_file(&prefs_path).is_some() {
+    // A temp file next to a good (or absent) file is from an interrupted write.
+    let _ = std::fs::remove_file(&temp_path);
+    return None;
+  }
+  // Unreadable (rather than unparseable) files are reported as a
+  // `preferences-warning` instead.
+  std::fs::read(&prefs_path).ok()?;
+
+  let corrupt_path = prefs_path.with_extension("json.corrupt");
+  let preserved = std::fs::rename(&prefs_path, &corrupt_path)
+    .ok()
+    .map(|_| corrupt_path.to_string_lossy().to_string());
+  let candidates = [prefs_path.with_extension("json.bak"), temp_path.clone()];
+  let recovered = candidates
+    .iter()
+    .find_map(|path| parse_preferences_file(path).map(|prefs| (path, prefs)));
+  let source = match recovered {
+    Some((path, prefs)) => match write_hub_preferences(&prefs) {
+      Ok(()) => Some(path.to_string_lossy().to_string()),
+      Err(error) => {
+        write_log(
+          "warning",
+          &format!(
+            "could not restore settings from {}: {}",
+            path.display(),
+            error
+          ),
+        );
+        None
+      }
+    },
+    None => None,
+  };
+  let _ = std::fs::remove_file(&temp_path);
+  Some(PreferencesRecovery { source, preserved })
+}
+
+fn current_preferences(state: &AppState) -> HubPreferences {
+  state
+    .prefs
+    .lock()
+    .map(|prefs| prefs.clone())
+    .unwrap_or_default()
+}
+
+fn load_ca_certificate(path: &Path) -> Result<reqwest::Certificate, String> {
+  let data = std::fs::read(path)
+    .map_err(|e| format!("Could not read CA certificate {}: {}", path.display(), e))?;
+  let invalid = |e: reqwest::Error| format!("Invalid CA certificate {}: {}", path.display(), e);
+  let certificate = reqwest::Certificate::from_pem(&data).map_err(invalid)?;
+  // rustls only parses the PEM when a client is built, so build one to reject bad files early.
+  Client::builder()
+    .add_root_certificate(certificate.clone())
+    .build()
+    .map_err(invalid)?;
+  Ok(certificate)
+}
+
+/// The certificates in a PEM file as DER, for a pinned client's root store.
+fn load_ca_certificate_ders(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+  let data = std::fs::read(path)
+    .map_err(|e| format!("Could not read CA certificate {}: {}", path.display(), e))?;
+  CertificateDer::pem_slice_iter(&data)
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Invalid CA certificate {}: {}", path.display(), e))
+}
+
+/// Lowercases an allowlist entry and drops a leading `*.` or `.`, which mean
+/// the same thing as the bare domain here.
+fn normalize_allowed_host(host: &str) -> String {
+  let host = host.trim().to_ascii_lowercase();
+  let host = host.strip_prefix("*.").unwrap_or(&host);
+  host.strip_prefix('.').unwrap_or(host).to_string()
+}
+
+/// Rejects `url` unless its host is on the download allowlist, matching the
+/// exact host or any subdomain of an entry. An empty allowlist allows any
+/// host.
+fn check_download_allowed(prefs: &HubPreferences, url: &str) -> Result<(), String> {
+  check_host_allowed(&prefs.download_host_allowlist, url)
+}
+
+fn check_host_allowed(allowlist: &[String], url: &str) -> Result<(), String> {
+  if allowlist.is_empty() {
+    return Ok(());
+  }
+  let parsed = reqwest::Url::parse(url).map_err(|_| format!("Invalid download URL: {}", url))?;
+  let host = parsed
+    .host_str()
+    .map(|host| host.trim_end_matches('.').to_ascii_lowercase())
+    .ok_or_else(|| format!("Download URL has no host: {}", url))?;
+  let allowed = allowlist.iter().any(|entry| {
+    let entry = normalize_allowed_host(entry);
+    host == entry || host.ends_with(&format!(".{}", entry))
+  });
+  if allowed {
+    Ok(())
+  } else {
+    Err(format!(
+      "Downloads from {} are not allowed by the host allowlist.",
+      host
+    ))
+  }
+}
+
+#[tauri::command]
+fn validate_download_url(url: String, state: tauri::State<AppState>) -> Result<(), String> {
+  check_download_allowed(&current_preferences(&state), &url)
+}
+
+/// Idle connections kept open per host for reuse by later requests.
+const HTTP_POOL_MAX_IDLE_PER_HOST: usize = 4;
+const HTTP_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+const DEFAULT_MAX_REDIRECTS: u32 = 10;
+const MAX_REDIRECTS_LIMIT: u32 = 50;
+
+#[derive(Debug)]
+struct TooManyRedirects(u32);
+
+impl std::fmt::Display for TooManyRedirects {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "too many redirects (limit {})", self.0)
+  }
+}
+
+impl std::error::Error for TooManyRedirects {}
+
+/// Follows up to `max_redirects` redirects, refusing any hop to a host the
+/// allowlist does not cover. When `history` is given, it holds the URLs the
+/// latest request was redirected through, in order.
+fn redirect_policy(
+  max_redirects: u32,
+  allowlist: Vec<String>,
+  history: Option<Arc<Mutex<Vec<String>>>>,
+) -> reqwest::redirect::Policy {
+  reqwest::redirect::Policy::custom(move |attempt| {
+    if let Some(history) = history.as_ref() {
+      if let Ok(mut history) = history.lock() {
+        *history = attempt
+          .previous()
+          .iter()
+          .skip(1)
+          .chain(std::iter::once(attempt.url()))
+          .map(|url| url.to_string())
+          .collect();
+      }
+    }
+    if attempt.previous().len() > max_redirects as usize {
+      attempt.error(TooManyRedirects(max_redirects))
+    } else if let Err(message) = check_host_allowed(&allowlist, attempt.url().as_str()) {
+      attempt.error(message)
+    } else {
+      attempt.follow()
+    }
+  })
+}
+
+/// Turns a failed request into a message, calling out redirect loops and
+/// redirects the allowlist refused.
+fn request_error_message(error: &reqwest::Error) -> String {
+  if !error.is_redirect() {
+    // reqwest stops at "error sending request"; the reason, such as a refused
+    // certificate pin, is at the bottom of the chain.
+    let mut cause = std::error::Error::source(error);
+    let mut deepest = None;
+    while let Some(inner) = cause {
+      deepest = Some(inner);
+      cause = inner.source();
+    }
+    return match deepest {
+      Some(inner) => format!("{}: {}", error, inner),
+      None => error.to_string(),
+    };
+  }
+  let url = error.url().map_or_else(String::new, |url| url.to_string());
+  let source = std::error::Error::source(error);
+  if source.is_some_and(|source| source.is::<TooManyRedirects>()) {
+    format!("Too many redirects while downloading {}.", url)
+  } else {
+    format!(
+      "Refused to follow a redirect to {}: {}",
+      url,
+      source.map_or_else(String::new, |source| source.to_string())
+    )
+  }
+}
+
+/// Verifies the server certificate as usual, then requires its public key to
+/// match a pin. This runs inside the TLS handshake, so a server that fails
+/// the pin never sees the request.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+  inner: Arc<rustls::client::WebPkiServerVerifier>,
+  /// Skips the usual chain checks for `danger_accept_invalid_certs`; the pin
+  /// still applies.
+  skip_chain: bool,
+  /// Base64 SHA-256 of the expected SubjectPublicKeyInfo.
+  spki_sha256: String,
+}
+
+impl PinnedCertVerifier {
+  fn new(roots: rustls::RootCertStore, skip_chain: bool, pin: &str) -> Result<Self, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let inner =
+      rustls::client::WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(PinnedCertVerifier {
+      inner,
+      skip_chain,
+      spki_sha256: pin.trim().to_string(),
+    })
+  }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+  fn verify_server_cert(
+    &self,
+    end_entity: &CertificateDer<'_>,
+    intermediates: &[CertificateDer<'_>],
+    server_name: &ServerName<'_>,
+    ocsp_response: &[u8],
+    now: UnixTime,
+  ) -> Result<ServerCertVerified, rustls::Error> {
+    if !self.skip_chain {
+      self
+        .inner
+        .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+    }
+    let actual = certificate_spki_sha256(end_entity).map_err(rustls::Error::General)?;
+    if actual == self.spki_sha256 {
+      Ok(ServerCertVerified::assertion())
+    } else {
+      Err(rustls::Error::General(format!(
+        "certificate pin mismatch: the server presented {}",
+        actual
+      )))
+    }
+  }
+
+  fn verify_tls12_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, rustls::Error> {
+    self.inner.verify_tls12_signature(message, cert, dss)
+  }
+
+  fn verify_tls13_signature(
+    &self,
+    message: &[u8],
+    cert: &CertificateDer<'_>,
+    dss: &DigitallySignedStruct,
+  ) -> Result<HandshakeSignatureValid, rustls::Error> {
+    self.inner.verify_tls13_signature(message, cert, dss)
+  }
+
+  fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+    self.inner.supported_verify_schemes()
+  }
+}
+
+/// The TLS setup `build_http_client` would use, with every server held to
+/// `pin` during the handshake.
+fn pinned_tls_config(prefs: &HubPreferences, pin: &str) -> Result<rustls::ClientConfig, String> {
+  let mut roots = rustls::RootCertStore {
+    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+  };
+  if let Some(path) = prefs.custom_ca_cert_path.as_deref() {
+    for certificate in load_ca_certificate_ders(Path::new(path))? {
+      roots.add(certificate).map_err(|e| e.to_string())?;
+    }
+  }
+  let verifier = PinnedCertVerifier::new(roots, prefs.danger_accept_invalid_certs, pin)?;
+  let provider = Arc::new(rustls::crypto::ring::default_provider());
+  Ok(
+    rustls::ClientConfig::builder_with_provider(provider)
+      .with_safe_default_protocol_versions()
+      .map_err(|e| e.to_string())?
+      .dangerous()
+      .with_custom_certificate_verifier(Arc::new(verifier))
+      .with_no_client_auth(),
+  )
+}
+
+/// Per-request additions to the client `build_http_client` makes.
+#[derive(Default)]
+struct ClientOptions<'a> {
+  /// Receives the redirect chain of the latest request sent through it.
+  redirects: Option<Arc<Mutex<Vec<String>>>>,
+  /// A pin (see `certificate_spki_sha256`) every server has to match.
+  spki_pin: Option<&'a str>,
+}
+
+fn build_http_client(prefs: &HubPreferences) -> Result<Client, String> {
+  build_http_client_with(prefs, ClientOptions::default())
+}
+
+fn build_http_client_with(
+  prefs: &HubPreferences,
+  options: ClientOptions,
+) -> Result<Client, String> {
+  let max_redirects = prefs.max_redirects.unwrap_or(DEFAULT_MAX_REDIRECTS);
+  let allowlist = prefs.download_host_allowlist.clone();
+  let policy = redirect_policy(max_redirects, allowlist, options.redirects);
+  let mut builder = Client::builder()
+    .user_agent(concat!("EnderfallHub/", env!("CARGO_PKG_VERSION")))
+    .pool_max_idle_per_host(HTTP_POOL_MAX_IDLE_PER_HOST)
+    .pool_idle_timeout(HTTP_POOL_IDLE_TIMEOUT)
+    .redirect(policy)
+    .tls_info(true);
+  if prefs.danger_accept_invalid_certs {
+    write_log(
+      "warning",
+      "downloading without TLS certificate verification",
+    );
+  }
+  if let Some(pin) = options.spki_pin {
+    builder = builder.use_preconfigured_tls(pinned_tls_config(prefs, pin)?);
+  } else {
+    if let Some(path) = prefs.custom_ca_cert_path.as_deref() {
+      builder = builder.add_root_certificate(load_ca_certificate(Path::new(path))?);
+    }
+    builder = builder.danger_accept_invalid_certs(prefs.danger_accept_invalid_certs);
+  }
+  builder.build().map_err(|e| e.to_string())
+}
+
+/// Returns the pooled client, building it from the current preferences the
+/// first time. Clones share one connection pool.
+fn shared_http_client(state: &AppState) -> Result<Client, String> {
+  let mut guard = state.http_client.lock().map_err(|e| e.to_string())?;
+  if let Some(client) = guard.as_ref() {
+    return Ok(client.clone());
+  }
+  let client = build_http_client(&current_preferences(state))?;
+  *guard = Some(client.clone());
+  Ok(client)
+}
+
+/// The client for one download: the shared one, or a client of its own
+/// when the download is pinned to a server key.
+fn download_client(state: &AppState, spki_pin: Option<&str>) -> Result<Client, String> {
+  match spki_pin {
+    Some(pin) => build_http_client_with(
+      &current_preferences(state),
+      ClientOptions {
+        spki_pin: Some(pin),
+        ..ClientOptions::default()
+      },
+    ),
+    None => shared_http_client(state),
+  }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct HubPreferencesUpdate {
   open_on_startup: Option<bool>,
   close_to_tray: Option<bool>,
   minimize_to_tray: Option<bool>,
+  /// An empty string clears the custom certificate.
+  custom_ca_cert_path: Option<String>,
+  danger_accept_invalid_certs: Option<bool>,
+  scan_downloads: Option<bool>,
+  defer_while_running: Option<bool>,
+  download_host_allowlist: Option<Vec<String>>,
+  notifications_enabled: Option<bool>,
+  /// One of `KNOWN_INSTALLER_TYPES`; an empty string clears it.
+  installer_type: Option<String>,
+  max_concurrent_installs: Option<u32>,
+  max_redirects: Option<u32>,
+  /// Turning this off also drops any reports still queued.
+  telemetry_enabled: Option<bool>,
+  /// An https URL; an empty string clears it.
+  telemetry_endpoint: Option<String>,
 }
 
 #[tauri::command]
@@ -73,12 +661,105 @@ fn get_hub_preferences() -> Result<HubPreferences, String> {
   Ok(load_hub_preferences())
 }
 
+/// Oldest entries are dropped once the history grows past this.
+const PREFERENCES_HISTORY_LIMIT: usize = 200;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreferenceChange {
+  field: String,
+  before: serde_json::Value,
+  after: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PreferencesHistoryEntry {
+  timestamp: u64,
+  changes: Vec<PreferenceChange>,
+}
+
+fn preferences_history_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("preferences-history.log"))
+}
+
+fn diff_preferences(before: &HubPreferences, after: &HubPreferences) -> Vec<PreferenceChange> {
+  let as_object = |prefs: &HubPreferences| match serde_json::to_value(prefs) {
+    Ok(serde_json::Value::Object(map)) => map,
+    _ => serde_json::Map::new(),
+  };
+  let (before, after) = (as_object(before), as_object(after));
+  let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+  fields.sort();
+  fields.dedup();
+  fields
+    .into_iter()
+    .filter_map(|field| {
+      let old = before.get(field).cloned().unwrap_or_default();
+      let new = after.get(field).cloned().unwrap_or_default();
+      (old != new).then(|| PreferenceChange {
+        field: field.clone(),
+        before: old,
+        after: new,
+      })
+    })
+    .collect()
+}
+
+fn read_preferences_history() -> Vec<PreferencesHistoryEntry> {
+  preferences_history_path()
+    .and_then(|path| std::fs::read_to_string(path).ok())
+    .map(|data| {
+      data
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Appends the changed fields to the history log, one JSON object per line.
+fn record_preferences_change(before: &HubPreferences, after: &HubPreferences) {
+  let changes = diff_preferences(before, after);
+  if changes.is_empty() {
+    return;
+  }
+  let Some(path) = preferences_history_path() else {
+    return;
+  };
+  let mut entries = read_preferences_history();
+  entries.push(PreferencesHistoryEntry {
+    timestamp: unix_timestamp(),
+    changes,
+  });
+  let skip = entries.len().saturating_sub(PREFERENCES_HISTORY_LIMIT);
+  let data: String = entries
+    .iter()
+    .skip(skip)
+    .filter_map(|entry| serde_json::to_string(entry).ok())
+    .map(|line| line + "\n")
+    .collect();
+  let _ = std::fs::write(path, data);
+}
+
+/// Returns recent preference changes, newest first.
+#[tauri::command]
+fn get_preferences_history() -> Vec<PreferencesHistoryEntry> {
+  let mut entries = read_preferences_history();
+  entries.reverse();
+  entries
+}
+
 #[tauri::command]
 fn set_hub_preferences(
   update: HubPreferencesUpdate,
   state: tauri::State<AppState>,
 ) -> Result<HubPreferences, String> {
-  let mut prefs = load_hub_preferences();
+  if let Some(dir) = hub_preferences_path().as_deref().and_then(Path::parent) {
+    ensure_dir_writable(dir)?;
+  }
+  let previous = load_hub_preferences();
+  let mut prefs = previous.clone();
   if let Some(value) = update.open_on_startup {
     prefs.open_on_startup = value;
   }
@@ -88,361 +769,8338 @@ fn set_hub_preferences(
   if let Some(value) = update.minimize_to_tray {
     prefs.minimize_to_tray = value;
   }
+  if let Some(value) = update.custom_ca_cert_path {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+      prefs.custom_ca_cert_path = None;
+    } else {
+      load_ca_certificate(Path::new(&value))?;
+      prefs.custom_ca_cert_path = Some(value);
+    }
+  }
+  if let Some(value) = update.installer_type {
+    let value = value.trim().to_ascii_lowercase();
+    if value.is_empty() {
+      prefs.installer_type = None;
+    } else if KNOWN_INSTALLER_TYPES.contains(&value.as_str()) {
+      prefs.installer_type = Some(value);
+    } else {
+      return Err(format!(
+        "Unknown installer type \"{}\". Expected one of: {}.",
+        value,
+        KNOWN_INSTALLER_TYPES.join(", ")
+      ));
+    }
+  }
+  if let Some(value) = update.max_concurrent_installs {
+    if !(1..=MAX_CONCURRENT_INSTALLS_LIMIT).contains(&value) {
+      return Err(format!(
+        "Concurrent installs must be between 1 and {}.",
+        MAX_CONCURRENT_INSTALLS_LIMIT
+      ));
+    }
+    prefs.max_concurrent_installs = Some(value);
+  }
+  if let Some(value) = update.max_redirects {
+    if value > MAX_REDIRECTS_LIMIT {
+      return Err(format!(
+        "Redirects are limited to at most {}.",
+        MAX_REDIRECTS_LIMIT
+      ));
+    }
+    prefs.max_redirects = Some(value);
+  }
+  if let Some(value) = update.danger_accept_invalid_certs {
+    if value {
+      write_log(
+        "warning",
+        "TLS certificate verification has been disabled for downloads",
+      );
+    }
+    prefs.danger_accept_invalid_certs = value;
+  }
+  if let Some(value) = update.scan_downloads {
+    prefs.scan_downloads = value;
+  }
+  if let Some(value) = update.defer_while_running {
+    prefs.defer_while_running = value;
+  }
+  if let Some(value) = update.notifications_enabled {
+    prefs.notifications_enabled = value;
+  }
+  if let Some(value) = update.telemetry_enabled {
+    prefs.telemetry_enabled = value;
+  }
+  if let Some(value) = update.telemetry_endpoint {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+      prefs.telemetry_endpoint = None;
+    } else {
+      let url =
+        reqwest::Url::parse(&value).map_err(|e| format!("Invalid telemetry endpoint: {}", e))?;
+      if url.scheme() != "https" {
+        return Err("The telemetry endpoint must use https.".to_string());
+      }
+      prefs.telemetry_endpoint = Some(value);
+    }
+  }
+  if let Some(hosts) = update.download_host_allowlist {
+    prefs.download_host_allowlist = hosts
+      .iter()
+      .map(|host| normalize_allowed_host(host))
+      .filter(|host| !host.is_empty())
+      .collect();
+  }
   write_hub_preferences(&prefs)?;
+  record_preferences_change(&previous, &prefs);
   if let Ok(mut guard) = state.prefs.lock() {
     *guard = prefs.clone();
   }
+  state.install_slots.freed.notify_all();
+  if previous.telemetry_enabled && !prefs.telemetry_enabled {
+    clear_telemetry_queue();
+  }
+  let client_changed = previous.custom_ca_cert_path != prefs.custom_ca_cert_path
+    || previous.danger_accept_invalid_certs != prefs.danger_accept_invalid_certs
+    || previous.max_redirects != prefs.max_redirects
+    || previous.download_host_allowlist != prefs.download_host_allowlist;
+  if client_changed {
+    if let Ok(mut client) = state.http_client.lock() {
+      *client = None;
+    }
+  }
   Ok(prefs)
 }
 
-#[cfg(target_os = "windows")]
-fn apply_window_icon(app: &tauri::App) {
-  let icon_path = app
-    .path_resolver()
-    .resolve_resource("icons/icon.ico")
-    .or_else(|| {
-      let cwd = std::env::current_dir().ok()?;
-      let candidates = [
-        cwd.join("icons").join("icon.ico"),
-        cwd.join("src-tauri").join("icons").join("icon.ico"),
-        cwd.join("..").join("icons").join("icon.ico"),
-        cwd.join("..").join("src-tauri").join("icons").join("icon.ico"),
-      ];
-      candidates.into_iter().find(|path| path.exists())
-    });
-
-  if let (Some(window), Some(path)) = (app.get_window("main"), icon_path) {
-    let _ = window.set_icon(tauri::Icon::File(path));
-  }
+/// Only Windows reports notifications as blocked at the OS level.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+enum NotificationPermission {
+  Granted,
+  Denied,
+  /// Not asked yet; notifications stay in-app until the user opts in.
+  Default,
 }
 
+/// Windows lets users turn toasts off for every app or for the hub alone,
+/// and only the Settings app can turn them back on.
 #[cfg(target_os = "windows")]
-fn create_shortcut(shortcut_path: &Path, target_path: &Path, working_dir: &Path) -> Result<(), String> {
-  if let Some(parent) = shortcut_path.parent() {
-    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+fn os_notifications_blocked(identifier: &str) -> bool {
+  let disabled = |key: &str, name: &str| {
+    registry_value(key, name).is_some_and(|value| value.trim().eq_ignore_ascii_case("0x0"))
+  };
+  disabled(
+    r"HKCU\Software\Microsoft\Windows\CurrentVersion\PushNotifications",
+    "ToastEnabled",
+  ) || disabled(
+    &format!(
+      r"HKCU\Software\Microsoft\Windows\CurrentVersion\Notifications\Settings\{}",
+      identifier
+    ),
+    "Enabled",
+  )
+}
+
+fn notification_permission(app: &tauri::AppHandle) -> NotificationPermission {
+  #[cfg(target_os = "windows")]
+  {
+    if os_notifications_blocked(&app.config().tauri.bundle.identifier) {
+      return NotificationPermission::Denied;
+    }
+  }
+  if current_preferences(&app.state::<AppState>()).notifications_enabled {
+    NotificationPermission::Granted
+  } else {
+    NotificationPermission::Default
   }
-  let script = format!(
-    "$W = New-Object -ComObject WScript.Shell; $S = $W.CreateShortcut('{}'); $S.TargetPath = '{}'; $S.WorkingDirectory = '{}'; $S.Save();",
-    shortcut_path.display(),
-    target_path.display(),
-    working_dir.display()
-  );
-  std::process::Command::new("powershell")
-    .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-Command", &script])
-    .status()
-    .map_err(|e| e.to_string())?;
-  Ok(())
 }
 
 #[tauri::command]
-fn path_exists(path: String) -> bool {
-  Path::new(&path).exists()
+fn check_notification_permission(app: tauri::AppHandle) -> NotificationPermission {
+  notification_permission(&app)
 }
 
+/// Opts in to OS notifications and sends a first one, which is what makes
+/// macOS show its permission prompt. When Windows has notifications turned
+/// off, opens the Settings page where the user can allow them instead.
 #[tauri::command]
-fn copy_installer(
-  window: tauri::Window,
-  app_id: String,
-  source_path: String,
-  destination_dir: String,
-) -> Result<String, String> {
-  let source = PathBuf::from(&source_path);
-  if !source.exists() {
-    return Err("Installer not found.".to_string());
+fn request_notification_permission(
+  app: tauri::AppHandle,
+  state: tauri::State<AppState>,
+) -> Result<NotificationPermission, String> {
+  let previous = current_preferences(&state);
+  if !previous.notifications_enabled {
+    let mut prefs = previous.clone();
+    prefs.notifications_enabled = true;
+    write_hub_preferences(&prefs)?;
+    record_preferences_change(&previous, &prefs);
+    if let Ok(mut guard) = state.prefs.lock() {
+      *guard = prefs;
+    }
   }
-
-  let file_name = source
-    .file_name()
-    .ok_or_else(|| "Installer filename missing.".to_string())?;
-  let destination = PathBuf::from(&destination_dir).join(file_name);
-
-  let mut input = File::open(&source).map_err(|e| e.to_string())?;
-  let total = input.metadata().map_err(|e| e.to_string())?.len();
-  let mut output = File::create(&destination).map_err(|e| e.to_string())?;
-  let mut buffer = vec![0u8; 1024 * 1024];
-  let mut copied: u64 = 0;
-
-  loop {
-    let read = input.read(&mut buffer).map_err(|e| e.to_string())?;
-    if read == 0 {
-      break;
+  let permission = notification_permission(&app);
+  match permission {
+    NotificationPermission::Denied => {
+      #[cfg(target_os = "windows")]
+      {
+        let _ = std::process::Command::new("explorer")
+          .arg("ms-settings:notifications")
+          .spawn();
+      }
     }
-    output.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
-    copied += read as u64;
-    let progress = if total == 0 { 1.0 } else { copied as f64 / total as f64 };
-    let _ = window.emit(
-      "installer-progress",
-      serde_json::json!({
-        "appId": app_id,
-        "progress": progress,
-      }),
-    );
+    _ => notify(&app, "Enderfall Hub", "Notifications are on."),
   }
+  Ok(permission)
+}
 
-  output.flush().map_err(|e| e.to_string())?;
-
-  Ok(destination.to_string_lossy().to_string())
+/// Shows an OS notification when the user has allowed them, and otherwise
+/// (or if showing one fails) emits `hub-notification` for the UI to display.
+fn notify(app: &tauri::AppHandle, title: &str, body: &str) {
+  if notification_permission(app) == NotificationPermission::Granted {
+    let shown = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+      .title(title)
+      .body(body)
+      .show();
+    match shown {
+      Ok(()) => return,
+      Err(error) => write_log(
+        "warning",
+        &format!("could not show a notification: {}", error),
+      ),
+    }
+  }
+  let _ = app.emit_all(
+    "hub-notification",
+    serde_json::json!({ "title": title, "body": body }),
+  );
 }
 
 #[tauri::command]
-fn launch_path(path: String) -> Result<(), String> {
-  let target = PathBuf::from(&path);
-  if !target.exists() {
-    return Err("File not found.".to_string());
+fn send_notification(app: tauri::AppHandle, title: String, body: String) {
+  notify(&app, &title, &body);
+}
+
+fn bundled_icon_path(resolver: &tauri::PathResolver) -> Option<PathBuf> {
+  resolver.resolve_resource("icons/icon.ico").or_else(|| {
+    let cwd = std::env::current_dir().ok()?;
+    let candidates = [
+      cwd.join("icons").join("icon.ico"),
+      cwd.join("src-tauri").join("icons").join("icon.ico"),
+      cwd.join("..").join("icons").join("icon.ico"),
+      cwd
+        .join("..")
+        .join("src-tauri")
+        .join("icons")
+        .join("icon.ico"),
+    ];
+    candidates.into_iter().find(|path| path.exists())
+  })
+}
+
+#[cfg(target_os = "windows")]
+fn apply_window_icon(app: &tauri::App) {
+  let icon_path = bundled_icon_path(&app.path_resolver());
+  if let (Some(window), Some(path)) = (app.get_window("main"), icon_path) {
+    let _ = window.set_icon(tauri::Icon::File(path));
   }
-  std::process::Command::new(target)
-    .spawn()
-    .map_err(|e| e.to_string())?;
-  Ok(())
+}
+
+/// The main window's logical size in tauri.conf.json, before any UI scale.
+const DEFAULT_WINDOW_SIZE: (f64, f64) = (1440.0, 900.0);
+/// The window never opens larger than this share of the monitor.
+const MAX_WINDOW_SCREEN_FRACTION: f64 = 0.9;
+const MIN_WINDOW_SCALE: f64 = 0.5;
+const MAX_WINDOW_SCALE: f64 = 3.0;
+/// Logical screen width the UI is designed for. Wider screens (such as a 4K
+/// monitor left at 100% scaling) get a proportionally larger automatic scale.
+const REFERENCE_SCREEN_WIDTH: f64 = 1920.0;
+
+/// Picks a UI scale for `monitor` in quarter steps between 1 and 2.
+fn automatic_window_scale(monitor: &tauri::Monitor) -> f64 {
+  let logical_width = f64::from(monitor.size().width) / monitor.scale_factor();
+  ((logical_width / REFERENCE_SCREEN_WIDTH * 4.0).floor() / 4.0).clamp(1.0, 2.0)
+}
+
+fn effective_window_scale(window: &tauri::Window, prefs: &HubPreferences) -> f64 {
+  prefs.window_scale.unwrap_or_else(|| {
+    window
+      .primary_monitor()
+      .ok()
+      .flatten()
+      .map_or(1.0, |monitor| automatic_window_scale(&monitor))
+  })
+}
+
+/// Grows the default window size by `scale`, capped to what fits on the
+/// primary monitor, and centers the window.
+fn apply_default_window_size(window: &tauri::Window, scale: f64) {
+  let Ok(Some(monitor)) = window.primary_monitor() else {
+    return;
+  };
+  let screen = monitor.size();
+  let factor = monitor.scale_factor();
+  let max_width = f64::from(screen.width) / factor * MAX_WINDOW_SCREEN_FRACTION;
+  let max_height = f64::from(screen.height) / factor * MAX_WINDOW_SCREEN_FRACTION;
+  let width = (DEFAULT_WINDOW_SIZE.0 * scale).min(max_width);
+  let height = (DEFAULT_WINDOW_SIZE.1 * scale).min(max_height);
+  let _ = window.set_size(tauri::LogicalSize::new(width, height));
+  let _ = window.center();
+}
+
+/// Zooms the page; it has to be reapplied after every page load.
+fn apply_window_zoom(window: &tauri::Window, scale: f64) {
+  let _ = window.eval(&format!(
+    "document.documentElement.style.zoom = '{}';",
+    scale
+  ));
+}
+
+const MIN_ZOOM_LEVEL: f64 = 0.5;
+const MAX_ZOOM_LEVEL: f64 = 2.0;
+const ZOOM_STEP: f64 = 0.1;
+
+/// The page zoom: the UI scale times the user's zoom level.
+fn page_zoom(window: &tauri::Window, prefs: &HubPreferences) -> f64 {
+  effective_window_scale(window, prefs) * prefs.zoom_level.unwrap_or(1.0)
+}
+
+/// Clamps `level` into range, rounded to whole steps so repeated zooming
+/// lands back on 100%, then stores and applies it. Returns the level now in
+/// effect; a value that is not a number leaves the zoom as it was.
+fn store_zoom_level(window: &tauri::Window, state: &AppState, level: f64) -> Result<f64, String> {
+  let previous = current_preferences(state);
+  if !level.is_finite() {
+    return Ok(previous.zoom_level.unwrap_or(1.0));
+  }
+  let level = ((level / ZOOM_STEP).round() * ZOOM_STEP).clamp(MIN_ZOOM_LEVEL, MAX_ZOOM_LEVEL);
+  let mut prefs = previous.clone();
+  // 100% is the default, so it is not stored.
+  prefs.zoom_level = ((level - 1.0).abs() > f64::EPSILON).then_some(level);
+  write_hub_preferences(&prefs)?;
+  record_preferences_change(&previous, &prefs);
+  let zoom = page_zoom(window, &prefs);
+  if let Ok(mut guard) = state.prefs.lock() {
+    *guard = prefs;
+  }
+  apply_window_zoom(window, zoom);
+  Ok(level)
 }
 
 #[tauri::command]
-fn run_installer(path: String, args: Vec<String>) -> Result<(), String> {
-  let target = PathBuf::from(&path);
-  if !target.exists() {
-    return Err("Installer not found.".to_string());
+fn set_zoom(
+  window: tauri::Window,
+  level: f64,
+  state: tauri::State<AppState>,
+) -> Result<f64, String> {
+  store_zoom_level(&window, &state, level)
+}
+
+#[tauri::command]
+fn zoom_in(window: tauri::Window, state: tauri::State<AppState>) -> Result<f64, String> {
+  let level = current_preferences(&state).zoom_level.unwrap_or(1.0);
+  store_zoom_level(&window, &state, level + ZOOM_STEP)
+}
+
+#[tauri::command]
+fn zoom_out(window: tauri::Window, state: tauri::State<AppState>) -> Result<f64, String> {
+  let level = current_preferences(&state).zoom_level.unwrap_or(1.0);
+  store_zoom_level(&window, &state, level - ZOOM_STEP)
+}
+
+/// Sets the UI scale, or with `None` goes back to following the monitor.
+/// Returns the scale now in effect.
+#[tauri::command]
+fn set_window_scale(
+  window: tauri::Window,
+  scale: Option<f64>,
+  state: tauri::State<AppState>,
+) -> Result<f64, String> {
+  if let Some(scale) = scale {
+    if !(MIN_WINDOW_SCALE..=MAX_WINDOW_SCALE).contains(&scale) {
+      return Err(format!(
+        "Scale must be between {} and {}.",
+        MIN_WINDOW_SCALE, MAX_WINDOW_SCALE
+      ));
+    }
   }
-  let status = std::process::Command::new(target)
-    .args(&args)
-    .status()
-    .map_err(|e| e.to_string())?;
-  if status.success() {
+  let previous = current_preferences(&state);
+  let mut prefs = previous.clone();
+  prefs.window_scale = scale;
+  write_hub_preferences(&prefs)?;
+  record_preferences_change(&previous, &prefs);
+  let effective = effective_window_scale(&window, &prefs);
+  let zoom = page_zoom(&window, &prefs);
+  if let Ok(mut guard) = state.prefs.lock() {
+    *guard = prefs;
+  }
+  apply_window_zoom(&window, zoom);
+  Ok(effective)
+}
+
+/// Values accepted for the `theme` preference.
+const KNOWN_THEMES: &[&str] = &["light", "dark", "system"];
+/// Background painted behind the page for each resolved theme, as ARGB hex.
+const LIGHT_BACKGROUND: &str = "FFFFFFFF";
+const DARK_BACKGROUND: &str = "FF101018";
+
+/// Whether the OS is set to dark mode, for when no window can be asked yet.
+fn system_prefers_dark() -> bool {
+  #[cfg(target_os = "windows")]
+  {
+    registry_value(
+      r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+      "AppsUseLightTheme",
+    )
+    .is_some_and(|value| value == "0x0")
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    false
+  }
+}
+
+/// Resolves the `theme` preference to "light" or "dark". "system" and unset
+/// follow `window`'s theme, or the OS setting when there is no window.
+fn resolved_theme(window: Option<&tauri::Window>, prefs: &HubPreferences) -> &'static str {
+  match prefs.theme.as_deref() {
+    Some("light") => return "light",
+    Some("dark") => return "dark",
+    _ => {}
+  }
+  let dark = match window.map(|window| window.theme()) {
+    Some(Ok(theme)) => matches!(theme, tauri::Theme::Dark),
+    _ => system_prefers_dark(),
+  };
+  if dark {
+    "dark"
+  } else {
+    "light"
+  }
+}
+
+fn theme_background(theme: &str) -> &'static str {
+  if theme == "dark" {
+    DARK_BACKGROUND
+  } else {
+    LIGHT_BACKGROUND
+  }
+}
+
+/// Makes WebView2 paint `theme`'s background before any content loads. It
+/// is read when a webview is created, so it has to be set before the window
+/// is built.
+#[cfg(target_os = "windows")]
+fn set_default_webview_background(theme: &str) {
+  std::env::set_var("WEBVIEW2_DEFAULT_BACKGROUND_COLOR", theme_background(theme));
+}
+
+/// Colors the page behind the UI so navigations do not flash white, and
+/// switches the tray to `icons/tray-<theme>.ico` when that icon is bundled.
+/// Like the zoom, the page part has to be reapplied after every page load.
+fn apply_window_theme(window: &tauri::Window, theme: &str) {
+  let background = theme_background(theme);
+  let _ = window.eval(&format!(
+    "document.documentElement.style.colorScheme = '{}'; document.documentElement.style.backgroundColor = '#{}';",
+    theme,
+    &background[2..]
+  ));
+  #[cfg(feature = "system-tray")]
+  if let Some(icon) = window
+    .app_handle()
+    .path_resolver()
+    .resolve_resource(format!("icons/tray-{}.ico", theme))
+    .filter(|path| path.exists())
+  {
+    let _ = window
+      .app_handle()
+      .tray_handle()
+      .set_icon(tauri::Icon::File(icon));
+  }
+}
+
+/// Sets the theme ("light", "dark" or "system") and applies it to the open
+/// window. Returns the theme now in effect, "light" or "dark".
+#[tauri::command]
+fn set_theme(
+  window: tauri::Window,
+  theme: String,
+  state: tauri::State<AppState>,
+) -> Result<String, String> {
+  let theme = theme.trim().to_ascii_lowercase();
+  if !KNOWN_THEMES.contains(&theme.as_str()) {
+    return Err(format!(
+      "Unknown theme \"{}\". Expected one of: {}.",
+      theme,
+      KNOWN_THEMES.join(", ")
+    ));
+  }
+  let previous = current_preferences(&state);
+  let mut prefs = previous.clone();
+  prefs.theme = Some(theme);
+  write_hub_preferences(&prefs)?;
+  record_preferences_change(&previous, &prefs);
+  let resolved = resolved_theme(Some(&window), &prefs);
+  if let Ok(mut guard) = state.prefs.lock() {
+    *guard = prefs;
+  }
+  apply_window_theme(&window, resolved);
+  Ok(resolved.to_string())
+}
+
+const ICON_MIN_SIZE: u32 = 16;
+const ICON_MAX_SIZE: u32 = 1024;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Checks that `path` is an ICO or PNG whose (largest) image is between
+/// `ICON_MIN_SIZE` and `ICON_MAX_SIZE` pixels on each side.
+fn validate_icon_file(path: &Path) -> Result<(), String> {
+  let data = std::fs::read(path).map_err(|e| e.to_string())?;
+  let (width, height) = if data.starts_with(&PNG_SIGNATURE) {
+    let dimension = |at: usize| {
+      data
+        .get(at..at + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    // The IHDR chunk always comes first and starts with width and height.
+    match (&data.get(12..16), dimension(16), dimension(20)) {
+      (Some(b"IHDR"), Some(width), Some(height)) => (width, height),
+      _ => return Err("The PNG icon is truncated.".to_string()),
+    }
+  } else if data.starts_with(&[0, 0, 1, 0]) {
+    let count = data
+      .get(4..6)
+      .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]));
+    // An ICO directory entry stores 0 for a 256-pixel side.
+    let side = |value: u8| if value == 0 { 256 } else { u32::from(value) };
+    (0..usize::from(count.unwrap_or(0)))
+      .filter_map(|index| data.get(6 + index * 16..8 + index * 16))
+      .map(|entry| (side(entry[0]), side(entry[1])))
+      .max()
+      .ok_or_else(|| "The ICO file contains no images.".to_string())?
+  } else {
+    return Err("Icons must be .ico or .png files.".to_string());
+  };
+  let in_range = |side: u32| (ICON_MIN_SIZE..=ICON_MAX_SIZE).contains(&side);
+  if in_range(width) && in_range(height) {
     Ok(())
   } else {
     Err(format!(
-      "Installer exited with code {:?}.",
-      status.code()
+      "Icon is {}x{}; it must be between {} and {} pixels on each side.",
+      width, height, ICON_MIN_SIZE, ICON_MAX_SIZE
     ))
   }
 }
 
+/// Returns a local file for `path_or_url`, downloading URLs into the icon
+/// cache first.
+fn resolve_icon_source(state: &AppState, path_or_url: &str) -> Result<PathBuf, String> {
+  let source = path_or_url.trim();
+  if !(source.starts_with("https://") || source.starts_with("http://")) {
+    let path = PathBuf::from(source);
+    if !path.exists() {
+      return Err("Icon file not found.".to_string());
+    }
+    return Ok(path);
+  }
+  check_download_allowed(&current_preferences(state), source)?;
+  let dir = hub_cache_dir()
+    .ok_or("Missing local data dir")?
+    .join("icons");
+  std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+  let extension = installer_file_name(source)
+    .rsplit_once('.')
+    .map(|(_, ext)| ext.to_ascii_lowercase())
+    .filter(|ext| ext == "ico" || ext == "png")
+    .unwrap_or_else(|| "ico".to_string());
+  let mut hasher = Sha256::new();
+  hasher.update(source.as_bytes());
+  let destination = dir.join(format!("{:x}.{}", hasher.finalize(), extension));
+  if !destination.exists() {
+    let client = shared_http_client(state)?;
+    download_to_file(&client, source, &destination, None, |_, _| {})?;
+  }
+  Ok(destination)
+}
+
+fn apply_icon(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+  if let Some(window) = app.get_window("main") {
+    window
+      .set_icon(tauri::Icon::File(path.to_path_buf()))
+      .map_err(|e| e.to_string())?;
+  }
+  #[cfg(feature = "system-tray")]
+  app
+    .tray_handle()
+    .set_icon(tauri::Icon::File(path.to_path_buf()))
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+/// Loads an .ico or .png from a local path or URL (cached under the hub's
+/// cache folder) and applies it to the main window and the tray. When the
+/// icon cannot be loaded or applied, the bundled icon is restored and the
+/// error is returned.
 #[tauri::command]
-fn run_dev_app(cwd: String, command: Vec<String>) -> Result<(), String> {
-  if command.is_empty() {
-    return Err("Missing dev command.".to_string());
+fn set_window_icon(
+  app: tauri::AppHandle,
+  path_or_url: String,
+  state: tauri::State<AppState>,
+) -> Result<String, String> {
+  let result = resolve_icon_source(&state, &path_or_url).and_then(|path| {
+    validate_icon_file(&path)?;
+    apply_icon(&app, &path)?;
+    Ok(path)
+  });
+  match result {
+    Ok(path) => Ok(path.to_string_lossy().to_string()),
+    Err(error) => {
+      if let Some(default) = bundled_icon_path(&app.path_resolver()) {
+        let _ = apply_icon(&app, &default);
+      }
+      Err(format!("Could not apply icon: {}", error))
+    }
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn create_shortcut(
+  shortcut_path: &Path,
+  target_path: &Path,
+  working_dir: &Path,
+) -> Result<(), String> {
+  if let Some(parent) = shortcut_path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let script = format!(
+    "$W = New-Object -ComObject WScript.Shell; $S = $W.CreateShortcut('{}'); $S.TargetPath = '{}'; $S.WorkingDirectory = '{}'; $S.Save();",
+    shortcut_path.display(),
+    target_path.display(),
+    working_dir.display()
+  );
+  std::process::Command::new("powershell")
+    .args([
+      "-NoProfile",
+      "-ExecutionPolicy",
+      "Bypass",
+      "-Command",
+      &script,
+    ])
+    .status()
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+/// The hub's folder under the current user's Start Menu programs.
+fn start_menu_dir() -> Option<PathBuf> {
+  let appdata = std::env::var("APPDATA").ok()?;
+  Some(
+    PathBuf::from(appdata)
+      .join("Microsoft")
+      .join("Windows")
+      .join("Start Menu")
+      .join("Programs")
+      .join("Enderfall"),
+  )
+}
+
+/// Resolves the Start Menu folder and recreates it if it was removed.
+fn ensure_start_menu_dir() -> Result<PathBuf, String> {
+  let dir = start_menu_dir().ok_or_else(|| "APPDATA is not set.".to_string())?;
+  std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+  Ok(dir)
+}
+
+#[cfg(target_os = "windows")]
+fn registry_key_exists(key: &str) -> bool {
+  std::process::Command::new("reg")
+    .args(["query", key])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .map(|status| status.success())
+    .unwrap_or(false)
+}
+
+/// Reads a registry value through `reg query`, returning its data as printed
+/// (multi-string values come back with `\0` separators).
+#[cfg(target_os = "windows")]
+fn registry_value(key: &str, name: &str) -> Option<String> {
+  let output = std::process::Command::new("reg")
+    .args(["query", key, "/v", name])
+    .output()
+    .ok()?;
+  if !output.status.success() {
+    return None;
   }
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  stdout.lines().find_map(|line| {
+    let line = line.trim();
+    let rest = line.strip_prefix(name)?.trim_start();
+    let (kind, data) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    kind.starts_with("REG_").then(|| data.trim().to_string())
+  })
+}
+
+/// Salt mixed into the machine id so it cannot be matched against the raw
+/// identifier other software reports.
+const MACHINE_ID_SALT: &str = "enderfall-hub:machine-id:";
+
+fn raw_machine_identifier() -> Option<String> {
   #[cfg(target_os = "windows")]
-  let mut cmd = {
-    let mut cmd = std::process::Command::new("cmd");
-    cmd.arg("/c");
-    cmd.args(&command);
-    cmd
-  };
+  {
+    registry_value(r"HKLM\SOFTWARE\Microsoft\Cryptography", "MachineGuid")
+  }
   #[cfg(not(target_os = "windows"))]
-  let mut cmd = {
-    let mut cmd = std::process::Command::new(&command[0]);
-    if command.len() > 1 {
-      cmd.args(&command[1..]);
+  {
+    [
+      "/etc/machine-id",
+      "/var/lib/dbus/machine-id",
+      "/sys/class/dmi/id/product_uuid",
+    ]
+    .iter()
+    .filter_map(|path| std::fs::read_to_string(path).ok())
+    .map(|value| value.trim().to_string())
+    .find(|value| !value.is_empty())
+  }
+}
+
+/// Returns a stable per-machine id for license seats or opt-in analytics.
+/// It is a salted SHA-256 of the OS machine identifier (`MachineGuid` on
+/// Windows, `machine-id`/product UUID elsewhere), computed locally and never
+/// sent anywhere by the hub itself. The value survives reinstalling the hub.
+#[tauri::command]
+fn get_machine_id() -> Result<String, String> {
+  static MACHINE_ID: OnceLock<Option<String>> = OnceLock::new();
+  MACHINE_ID
+    .get_or_init(|| {
+      let raw = raw_machine_identifier()?;
+      let mut hasher = Sha256::new();
+      hasher.update(MACHINE_ID_SALT.as_bytes());
+      hasher.update(raw.trim().to_ascii_lowercase().as_bytes());
+      Some(format!("{:x}", hasher.finalize()))
+    })
+    .clone()
+    .ok_or_else(|| "Could not determine a machine identifier.".to_string())
+}
+
+/// Checks the registry markers Windows sets while a restart is outstanding.
+/// Installing on top of one commonly fails with 1618/3010.
+#[tauri::command]
+fn is_reboot_pending() -> bool {
+  #[cfg(target_os = "windows")]
+  {
+    let pending_renames = registry_value(
+      r"HKLM\SYSTEM\CurrentControlSet\Control\Session Manager",
+      "PendingFileRenameOperations",
+    )
+    .is_some_and(|data| !data.is_empty());
+    pending_renames
+      || registry_key_exists(
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\Component Based Servicing\RebootPending",
+      )
+      || registry_key_exists(
+        r"HKLM\SOFTWARE\Microsoft\Windows\CurrentVersion\WindowsUpdate\Auto Update\RebootRequired",
+      )
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    false
+  }
+}
+
+#[tauri::command]
+fn path_exists(path: String) -> bool {
+  Path::new(&path).exists()
+}
+
+/// Threads `paths_exist` spreads its checks over, so one slow (for example
+/// offline network) path does not hold up the rest.
+const PATH_CHECK_THREADS: usize = 8;
+
+/// Checks many paths in one call, answering in the same order. A path that
+/// cannot be checked counts as missing.
+#[tauri::command]
+fn paths_exist(paths: Vec<String>) -> Vec<bool> {
+  if paths.len() <= 1 {
+    return paths.iter().map(|path| Path::new(path).exists()).collect();
+  }
+  let chunk_size = paths.len().div_ceil(PATH_CHECK_THREADS);
+  std::thread::scope(|scope| {
+    let workers: Vec<_> = paths
+      .chunks(chunk_size)
+      .map(|chunk| {
+        scope.spawn(move || {
+          chunk
+            .iter()
+            .map(|path| Path::new(path).exists())
+            .collect::<Vec<_>>()
+        })
+      })
+      .collect();
+    workers
+      .into_iter()
+      .zip(paths.chunks(chunk_size))
+      .flat_map(|(worker, chunk)| worker.join().unwrap_or_else(|_| vec![false; chunk.len()]))
+      .collect()
+  })
+}
+
+/// Identifies the volume holding `path`: the serial number of the volume it
+/// is mounted from on Windows, the device id elsewhere.
+#[cfg(target_os = "windows")]
+fn volume_id(path: &Path) -> Option<u64> {
+  use std::os::windows::ffi::OsStrExt;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn GetVolumePathNameW(file_name: *const u16, volume_path: *mut u16, length: u32) -> i32;
+    fn GetVolumeInformationW(
+      root_path: *const u16,
+      volume_name: *mut u16,
+      volume_name_size: u32,
+      serial_number: *mut u32,
+      max_component_length: *mut u32,
+      file_system_flags: *mut u32,
+      file_system_name: *mut u16,
+      file_system_name_size: u32,
+    ) -> i32;
+  }
+
+  let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+  let mut root = [0u16; MAX_PATH_CHARS + 1];
+  let mut serial = 0u32;
+  // SAFETY: both buffers are NUL-terminated or sized as passed, and the
+  // optional out-parameters are null with zero lengths.
+  unsafe {
+    if GetVolumePathNameW(wide.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+      return None;
     }
-    cmd
+    if GetVolumeInformationW(
+      root.as_ptr(),
+      std::ptr::null_mut(),
+      0,
+      &mut serial,
+      std::ptr::null_mut(),
+      std::ptr::null_mut(),
+      std::ptr::null_mut(),
+      0,
+    ) == 0
+    {
+      return None;
+    }
+  }
+  Some(u64::from(serial))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn volume_id(path: &Path) -> Option<u64> {
+  use std::os::unix::fs::MetadataExt;
+  std::fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+/// Whether a rename from one path to the other stays on one volume (and is
+/// therefore instant) rather than turning into a copy. Paths that do not exist
+/// yet are judged by their nearest existing parent; `None` when either volume
+/// cannot be determined.
+fn same_volume_paths(a: &Path, b: &Path) -> Option<bool> {
+  let volume = |path: &Path| {
+    let path = canonicalize_lenient(path);
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    volume_id(existing)
   };
-  cmd.current_dir(cwd);
-  cmd.spawn().map_err(|e| e.to_string())?;
-  Ok(())
+  Some(volume(a)? == volume(b)?)
 }
 
 #[tauri::command]
-fn create_shortcuts(
-  exe_path: String,
-  app_name: String,
-  create_desktop_shortcut: bool,
-  create_start_menu_shortcut: bool,
-) -> Result<(), String> {
-  let target = PathBuf::from(&exe_path);
-  if !target.exists() {
-    return Err("Executable not found.".to_string());
+fn same_volume(path_a: String, path_b: String) -> Result<bool, String> {
+  same_volume_paths(Path::new(&path_a), Path::new(&path_b))
+    .ok_or_else(|| "Could not determine the volume of one of the paths.".to_string())
+}
+
+/// Expands `%VAR%` references (and `$VAR` / `${VAR}` outside Windows).
+fn expand_env_vars(input: &str) -> Result<String, String> {
+  let lookup = |name: &str| {
+    std::env::var(name).map_err(|_| format!("Environment variable {} is not set.", name))
+  };
+  let mut output = String::with_capacity(input.len());
+  let mut rest = input;
+  while let Some(start) = rest.find('%') {
+    let Some(len) = rest[start + 1..].find('%') else {
+      break;
+    };
+    output.push_str(&rest[..start]);
+    let name = &rest[start + 1..start + 1 + len];
+    if name.is_empty() {
+      output.push('%');
+    } else {
+      output.push_str(&lookup(name)?);
+    }
+    rest = &rest[start + len + 2..];
   }
-  let working_dir = target
-    .parent()
-    .ok_or_else(|| "Executable directory missing.".to_string())?;
+  output.push_str(rest);
 
-  if create_desktop_shortcut {
-    if let Some(desktop) = tauri::api::path::desktop_dir() {
-      let shortcut = desktop.join(format!("{}.lnk", app_name));
-      create_shortcut(&shortcut, &target, working_dir)?;
+  if cfg!(target_os = "windows") {
+    return Ok(output);
+  }
+  let input = output;
+  let mut output = String::with_capacity(input.len());
+  let mut chars = input.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c != '$' {
+      output.push(c);
+      continue;
+    }
+    let braced = chars.peek() == Some(&'{');
+    if braced {
+      chars.next();
+    }
+    let mut name = String::new();
+    while let Some(&next) = chars.peek() {
+      if next.is_ascii_alphanumeric() || next == '_' {
+        name.push(next);
+        chars.next();
+      } else {
+        break;
+      }
+    }
+    if braced && chars.next() != Some('}') {
+      return Err("Unterminated ${...} in path.".to_string());
+    }
+    if name.is_empty() {
+      output.push('$');
+    } else {
+      output.push_str(&lookup(&name)?);
     }
   }
+  Ok(output)
+}
 
-  if create_start_menu_shortcut {
-    if let Ok(appdata) = std::env::var("APPDATA") {
-      let start_menu = PathBuf::from(appdata)
-        .join("Microsoft")
-        .join("Windows")
-        .join("Start Menu")
-        .join("Programs")
-        .join("Enderfall");
-      let shortcut = start_menu.join(format!("{}.lnk", app_name));
-      create_shortcut(&shortcut, &target, working_dir)?;
+/// Drops the `\\?\` prefix `canonicalize` adds on Windows so the path stays
+/// readable and usable by tools that do not understand verbatim paths.
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+  let text = path.to_string_lossy();
+  if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+    return PathBuf::from(format!(r"\\{}", rest));
+  }
+  if let Some(rest) = text.strip_prefix(r"\\?\") {
+    return PathBuf::from(rest);
+  }
+  path
+}
+
+/// How long a network share gets to answer before an operation on it is
+/// abandoned; an offline share can otherwise block file calls for minutes.
+const NETWORK_PATH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns `\\server\share` when `path` lives on a Windows network share,
+/// either as a UNC path or on a drive letter mapped to one.
+#[cfg(target_os = "windows")]
+fn network_share_root(path: &Path) -> Option<String> {
+  let text = strip_verbatim_prefix(path.to_path_buf())
+    .to_string_lossy()
+    .replace('/', "\\");
+  if let Some(rest) = text.strip_prefix(r"\\") {
+    if rest.starts_with(['?', '.']) {
+      return None;
     }
+    let mut segments = rest.split('\\').filter(|segment| !segment.is_empty());
+    let server = segments.next()?;
+    let share = segments.next()?;
+    return Some(format!(r"\\{}\{}", server, share));
   }
+  let drive = text.chars().next().filter(|c| c.is_ascii_alphabetic())?;
+  if !text[1..].starts_with(':') {
+    return None;
+  }
+  registry_value(&format!(r"HKCU\Network\{}", drive), "RemotePath")
+}
 
-  Ok(())
+/// Mounted shares look like any other folder outside Windows, so there is
+/// nothing to probe up front.
+#[cfg(not(target_os = "windows"))]
+fn network_share_root(_path: &Path) -> Option<String> {
+  None
+}
+
+/// Fails with a "network location unavailable" error when `path` is on a share
+/// that does not answer within `NETWORK_PATH_TIMEOUT`. Local paths pass
+/// straight through. The probe runs on its own thread so a hung share only
+/// costs the timeout.
+fn ensure_network_location_available(path: &Path) -> Result<(), String> {
+  let Some(root) = network_share_root(path) else {
+    return Ok(());
+  };
+  let (sender, receiver) = std::sync::mpsc::channel();
+  let probe = PathBuf::from(&root);
+  std::thread::spawn(move || {
+    let _ = sender.send(std::fs::metadata(&probe).map(|meta| meta.is_dir()));
+  });
+  match receiver.recv_timeout(NETWORK_PATH_TIMEOUT) {
+    Ok(Ok(true)) => Ok(()),
+    Ok(Ok(false)) | Ok(Err(_)) => Err(format!(
+      "Network location {} is unavailable. Check that the share exists and you have access to it.",
+      root
+    )),
+    Err(_) => Err(format!(
+      "Network location {} is unavailable: it did not respond within {} seconds.",
+      root,
+      NETWORK_PATH_TIMEOUT.as_secs()
+    )),
+  }
 }
 
+/// Reports the network share `path` lives on, if any, after checking that it
+/// answers. Local paths return `None`.
 #[tauri::command]
-fn uninstall_app(install_dir: String, app_name: String) -> Result<(), String> {
-  let install_path = PathBuf::from(&install_dir);
-  if install_path.exists() {
-    std::fs::remove_dir_all(&install_path).map_err(|e| e.to_string())?;
+fn check_network_location(path: String) -> Result<Option<String>, String> {
+  ensure_network_location_available(Path::new(&path))?;
+  Ok(network_share_root(Path::new(&path)))
+}
+
+/// Canonicalizes the deepest existing ancestor of `path` and re-appends the
+/// components that do not exist yet.
+fn canonicalize_lenient(path: &Path) -> PathBuf {
+  let mut existing = path.to_path_buf();
+  let mut missing = Vec::new();
+  loop {
+    if let Ok(canonical) = std::fs::canonicalize(&existing) {
+      let mut result = strip_verbatim_prefix(canonical);
+      for component in missing.iter().rev() {
+        result.push(component);
+      }
+      return result;
+    }
+    match (
+      existing.file_name().map(|name| name.to_os_string()),
+      existing.parent(),
+    ) {
+      (Some(name), Some(parent)) => {
+        missing.push(name);
+        existing = parent.to_path_buf();
+      }
+      _ => return path.to_path_buf(),
+    }
   }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NormalizedInstallDir {
+  path: String,
+  valid: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  reason: Option<String>,
+}
 
-  if let Some(desktop) = tauri::api::path::desktop_dir() {
-    let shortcut = desktop.join(format!("{}.lnk", app_name));
-    if shortcut.exists() {
-      let _ = std::fs::remove_file(shortcut);
+fn normalize_install_dir_input(input: &str) -> NormalizedInstallDir {
+  let invalid = |path: String, reason: String| NormalizedInstallDir {
+    path,
+    valid: false,
+    reason: Some(reason),
+  };
+  let trimmed = input.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+  if trimmed.is_empty() {
+    return invalid(String::new(), "Install location is empty.".to_string());
+  }
+  let expanded = match expand_env_vars(trimmed) {
+    Ok(expanded) => expanded,
+    Err(reason) => return invalid(trimmed.to_string(), reason),
+  };
+
+  let separator = std::path::MAIN_SEPARATOR;
+  let unc =
+    cfg!(target_os = "windows") && (expanded.starts_with(r"\\") || expanded.starts_with("//"));
+  let mut normalized = String::with_capacity(expanded.len());
+  for c in expanded.chars() {
+    let c = if cfg!(target_os = "windows") && c == '/' {
+      '\\'
+    } else {
+      c
+    };
+    if c == separator && normalized.ends_with(separator) {
+      continue;
+    }
+    normalized.push(c);
+  }
+  if unc {
+    normalized.insert(0, separator);
+  }
+  while normalized.len() > 1
+    && normalized.ends_with(separator)
+    && !normalized.ends_with(&format!(":{}", separator))
+  {
+    normalized.pop();
+  }
+
+  if cfg!(target_os = "windows") {
+    let body = normalized.get(2..).unwrap_or_default();
+    if body.contains(['<', '>', '"', '|', '?', '*', ':']) {
+      return invalid(
+        normalized,
+        "Path contains characters Windows does not allow.".to_string(),
+      );
+    }
+  }
+  let path = PathBuf::from(&normalized);
+  if !path.is_absolute() {
+    return invalid(
+      normalized,
+      "Install location must be an absolute path.".to_string(),
+    );
+  }
+  if path.is_file() {
+    return invalid(
+      normalized,
+      "Install location points to a file, not a folder.".to_string(),
+    );
+  }
+
+  NormalizedInstallDir {
+    path: canonicalize_lenient(&path).to_string_lossy().to_string(),
+    valid: true,
+    reason: None,
+  }
+}
+
+/// Cleans up an install location typed or pasted into the settings UI:
+/// surrounding quotes and whitespace are stripped, environment variables are
+/// expanded, separators follow the platform, and the result is made canonical.
+#[tauri::command]
+fn normalize_install_dir(input: String) -> NormalizedInstallDir {
+  normalize_install_dir_input(&input)
+}
+
+/// Upper bound on `installer-progress` events per operation.
+const PROGRESS_EVENTS_PER_SEC: u64 = 20;
+
+/// Fraction of `total` covered by `copied`, or `None` when the total is
+/// unknown (zero). Overshooting a wrong total stays at 1.0 rather than
+/// exceeding it.
+fn progress_fraction(copied: u64, total: u64) -> Option<f64> {
+  if total == 0 {
+    return None;
+  }
+  Some(copied.min(total) as f64 / total as f64)
+}
+
+/// Emits `installer-progress` for one `(app_id, operation_id)` operation,
+/// coalescing updates that arrive faster than `PROGRESS_EVENTS_PER_SEC`.
+/// Completion (progress 1.0) is always delivered. When the size of the work
+/// is unknown, events carry `progress: null` and the byte count instead.
+struct ProgressEmitter<'a> {
+  window: &'a tauri::Window,
+  app_id: &'a str,
+  operation_id: Option<&'a str>,
+  last_emit: Option<Instant>,
+  last_progress: Option<f64>,
+  pending: Option<(Option<&'static str>, Option<f64>)>,
+  bytes_per_second: Option<u64>,
+  bytes_transferred: Option<u64>,
+}
+
+impl<'a> ProgressEmitter<'a> {
+  fn new(window: &'a tauri::Window, app_id: &'a str, operation_id: Option<&'a str>) -> Self {
+    ProgressEmitter {
+      window,
+      app_id,
+      operation_id,
+      last_emit: None,
+      last_progress: None,
+      pending: None,
+      bytes_per_second: None,
+      bytes_transferred: None,
+    }
+  }
+
+  /// Attaches a transfer rate to the events that follow.
+  fn set_bytes_per_second(&mut self, bytes_per_second: Option<u64>) {
+    self.bytes_per_second = bytes_per_second;
+  }
+
+  fn update(&mut self, phase: Option<&'static str>, progress: f64) {
+    if progress.is_nan() {
+      return;
+    }
+    self.bytes_transferred = None;
+    self.queue(phase, Some(progress.clamp(0.0, 1.0)));
+  }
+
+  /// Reports bytes transferred so far for work whose total size is unknown.
+  fn update_indeterminate(&mut self, phase: Option<&'static str>, bytes: u64) {
+    self.bytes_transferred = Some(bytes);
+    self.queue(phase, None);
+  }
+
+  /// Reports `copied` of `total` bytes, falling back to an indeterminate
+  /// update when the total is unknown. `scale` maps the fraction into this
+  /// operation's share of the overall bar.
+  fn update_bytes(&mut self, phase: Option<&'static str>, copied: u64, total: u64, scale: f64) {
+    match progress_fraction(copied, total) {
+      Some(fraction) => {
+        self.bytes_transferred = Some(copied);
+        self.queue(phase, Some((fraction * scale).clamp(0.0, 1.0)));
+      }
+      None => self.update_indeterminate(phase, copied),
+    }
+  }
+
+  fn queue(&mut self, phase: Option<&'static str>, progress: Option<f64>) {
+    let interval = Duration::from_millis(1000 / PROGRESS_EVENTS_PER_SEC);
+    let throttled = self.last_emit.is_some_and(|last| last.elapsed() < interval);
+    if throttled && progress.is_none_or(|progress| progress < 1.0) {
+      self.pending = Some((phase, progress));
+      return;
+    }
+    self.emit(phase, progress);
+  }
+
+  /// Sends the last coalesced update, if any was held back.
+  fn flush(&mut self) {
+    if let Some((phase, progress)) = self.pending.take() {
+      self.emit(phase, progress);
+    }
+  }
+
+  /// Reports completion unless it has already been sent.
+  fn finish(&mut self, phase: Option<&'static str>) {
+    self.pending = None;
+    if self.last_progress != Some(1.0) {
+      self.emit(phase, Some(1.0));
+    }
+  }
+
+  fn emit(&mut self, phase: Option<&'static str>, progress: Option<f64>) {
+    let mut payload = serde_json::json!({
+      "appId": self.app_id,
+      "progress": progress,
+    });
+    if let Some(bytes) = self.bytes_transferred {
+      payload["bytes"] = bytes.into();
+    }
+    if let Some(operation_id) = self.operation_id {
+      payload["operationId"] = operation_id.into();
+    }
+    if let Some(phase) = phase {
+      payload["phase"] = phase.into();
+    }
+    if let Some(bytes_per_second) = self.bytes_per_second {
+      payload["bytesPerSecond"] = bytes_per_second.into();
+    }
+    let _ = self.window.emit("installer-progress", payload);
+    self.last_emit = Some(Instant::now());
+    if progress.is_some() {
+      self.last_progress = progress;
+    }
+    self.pending = None;
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopiedInstaller {
+  path: String,
+  /// Hash of the bytes copied, when one was asked for.
+  sha256: Option<String>,
+}
+
+/// Copies an installer into `destination_dir`. With `compute_sha256` or
+/// `expected_sha256` the copy is hashed as it is written; a copy that does
+/// not match `expected_sha256` is deleted.
+#[tauri::command]
+fn copy_installer(
+  window: tauri::Window,
+  app_id: String,
+  source_path: String,
+  destination_dir: String,
+  operation_id: Option<String>,
+  compute_sha256: Option<bool>,
+  expected_sha256: Option<String>,
+) -> Result<CopiedInstaller, String> {
+  let source = PathBuf::from(&source_path);
+  if !source.exists() {
+    return Err("Installer not found.".to_string());
+  }
+
+  let file_name = source
+    .file_name()
+    .ok_or_else(|| "Installer filename missing.".to_string())?;
+  let destination = PathBuf::from(&destination_dir).join(file_name);
+
+  let mut input = File::open(&source).map_err(|e| e.to_string())?;
+  let total = input.metadata().map_err(|e| e.to_string())?.len();
+  let mut output = File::create(&destination).map_err(|e| e.to_string())?;
+  let mut buffer = vec![0u8; 1024 * 1024];
+  let mut copied: u64 = 0;
+  let mut progress = ProgressEmitter::new(&window, &app_id, operation_id.as_deref());
+  let mut hasher = (compute_sha256.unwrap_or(false) || expected_sha256.is_some()).then(Sha256::new);
+
+  loop {
+    let read = input.read(&mut buffer).map_err(|e| e.to_string())?;
+    if read == 0 {
+      break;
+    }
+    output
+      .write_all(&buffer[..read])
+      .map_err(|e| e.to_string())?;
+    if let Some(hasher) = hasher.as_mut() {
+      hasher.update(&buffer[..read]);
+    }
+    copied += read as u64;
+    progress.update_bytes(None, copied, total, 1.0);
+  }
+
+  output.flush().map_err(|e| e.to_string())?;
+  drop(output);
+  let sha256 = hasher.map(|hasher| format!("{:x}", hasher.finalize()));
+  if let (Some(expected), Some(actual)) = (expected_sha256.as_deref(), sha256.as_deref()) {
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+      let _ = std::fs::remove_file(&destination);
+      return Err("Copied installer checksum mismatch.".to_string());
+    }
+  }
+  progress.finish(None);
+
+  Ok(CopiedInstaller {
+    path: destination.to_string_lossy().to_string(),
+    sha256,
+  })
+}
+
+/// Non-zero exits sooner than this after launch are reported as a likely crash.
+const STARTUP_CRASH_SECS: u64 = 5;
+const STDERR_TAIL_BYTES: usize = 4096;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct AppExitInfo {
+  app_id: String,
+  exit_code: Option<i32>,
+  exited_at: u64,
+  runtime_ms: u64,
+  likely_crash: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  stderr_tail: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  hint: Option<String>,
+}
+
+fn unix_timestamp() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0)
+}
+
+fn unix_timestamp_millis() -> u128 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_millis())
+    .unwrap_or(0)
+}
+
+/// Describes the common NTSTATUS codes a crashing Windows process exits with.
+fn exit_code_hint(code: i32) -> Option<&'static str> {
+  match code as u32 {
+    0xC000_0005 => Some("access violation"),
+    0xC000_007B => Some("invalid image format (32/64-bit mismatch)"),
+    0xC000_0135 => Some("a required DLL was not found"),
+    0xC000_0142 => Some("a DLL failed to initialize"),
+    0xC000_00FD => Some("stack overflow"),
+    0xC000_0409 => Some("stack buffer overrun"),
+    _ => None,
+  }
+}
+
+fn track_app_exit(app: tauri::AppHandle, app_id: String, mut child: Child) {
+  let started = Instant::now();
+  if let Ok(mut running) = app.state::<AppState>().running_apps.lock() {
+    running.push(app_id.clone());
+  }
+  std::thread::spawn(move || {
+    let mut stderr_tail = Vec::new();
+    if let Some(mut stderr) = child.stderr.take() {
+      let mut buffer = [0u8; 4096];
+      while let Ok(read) = stderr.read(&mut buffer) {
+        if read == 0 {
+          break;
+        }
+        stderr_tail.extend_from_slice(&buffer[..read]);
+        if stderr_tail.len() > STDERR_TAIL_BYTES {
+          let excess = stderr_tail.len() - STDERR_TAIL_BYTES;
+          stderr_tail.drain(..excess);
+        }
+      }
+    }
+    let exit_code = child.wait().ok().and_then(|status| status.code());
+    let runtime = started.elapsed();
+    record_app_runtime(&app_id, runtime.as_millis() as u64);
+    let stderr_tail = String::from_utf8_lossy(&stderr_tail).trim().to_string();
+    let info = AppExitInfo {
+      app_id: app_id.clone(),
+      exit_code,
+      exited_at: unix_timestamp(),
+      runtime_ms: runtime.as_millis() as u64,
+      likely_crash: exit_code != Some(0) && runtime.as_secs() < STARTUP_CRASH_SECS,
+      stderr_tail: Some(stderr_tail).filter(|tail| !tail.is_empty()),
+      hint: exit_code.and_then(exit_code_hint).map(str::to_string),
+    };
+    if let Some(state) = app.try_state::<AppState>() {
+      if let Ok(mut running) = state.running_apps.lock() {
+        if let Some(index) = running.iter().position(|id| *id == app_id) {
+          running.remove(index);
+        }
+      }
+      if let Ok(mut exits) = state.exits.lock() {
+        exits.insert(app_id, info.clone());
+      }
+    }
+    let _ = app.emit_all("app-exited", info);
+  });
+}
+
+/// Download rate allowed while a launched app is running and
+/// `defer_while_running` is on.
+const DEFERRED_DOWNLOAD_BYTES_PER_SEC: u64 = 512 * 1024;
+/// How often a paused extraction checks whether the running apps have exited.
+const DEFERRED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest a paused extraction waits before the install gives up.
+const DEFERRED_WAIT_LIMIT: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Highest value accepted for `max_concurrent_installs`.
+const MAX_CONCURRENT_INSTALLS_LIMIT: u32 = 8;
+/// How often a waiting install rechecks the limit, so raising it takes effect.
+const INSTALL_SLOT_RECHECK: Duration = Duration::from_secs(1);
+
+/// Counts installs that are extracting or creating shortcuts so no more than
+/// `max_concurrent_installs` do so at once.
+#[derive(Default)]
+struct InstallSlots {
+  active: Mutex<u32>,
+  freed: Condvar,
+}
+
+/// Held for the duration of one install's disk work; dropping it lets the
+/// next waiting install start.
+struct InstallSlot<'a> {
+  slots: &'a InstallSlots,
+}
+
+impl InstallSlots {
+  /// Blocks until an install slot is free. Emits `install-waiting` once if
+  /// the install has to queue behind others.
+  fn acquire<'a>(&'a self, app: &tauri::AppHandle, app_id: &str) -> InstallSlot<'a> {
+    let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+    let mut waiting = false;
+    loop {
+      let limit = current_preferences(&app.state::<AppState>())
+        .max_concurrent_installs
+        .unwrap_or(1)
+        .max(1);
+      if *active < limit {
+        *active += 1;
+        return InstallSlot { slots: self };
+      }
+      if !waiting {
+        waiting = true;
+        let _ = app.emit_all(
+          "install-waiting",
+          serde_json::json!({ "appId": app_id, "active": *active, "limit": limit }),
+        );
+      }
+      active = self
+        .freed
+        .wait_timeout(active, INSTALL_SLOT_RECHECK)
+        .map(|(guard, _)| guard)
+        .unwrap_or_else(|e| e.into_inner().0);
+    }
+  }
+}
+
+impl Drop for InstallSlot<'_> {
+  fn drop(&mut self) {
+    if let Ok(mut active) = self.slots.active.lock() {
+      *active = active.saturating_sub(1);
+    }
+    self.slots.freed.notify_all();
+  }
+}
+
+/// Holds back background work for `app_id` while apps launched from the hub
+/// are running: downloads are throttled and extractions wait to start. Emits
+/// `work-deferred` when it starts holding back and `work-resumed` afterwards.
+struct Deferral<'a> {
+  app: &'a tauri::AppHandle,
+  app_id: &'a str,
+  enabled: bool,
+  deferred: bool,
+  last_check: Instant,
+  last_bytes: Option<u64>,
+}
+
+impl<'a> Deferral<'a> {
+  fn new(app: &'a tauri::AppHandle, app_id: &'a str) -> Self {
+    // A cancel meant for an earlier install must not stop this one.
+    if let Ok(mut cancelled) = app.state::<AppState>().cancelled_deferrals.lock() {
+      cancelled.remove(app_id);
+    }
+    Deferral {
+      app,
+      app_id,
+      enabled: current_preferences(&app.state::<AppState>()).defer_while_running,
+      deferred: false,
+      last_check: Instant::now(),
+      last_bytes: None,
+    }
+  }
+
+  /// Running launched apps, or nothing when the preference is off.
+  fn running_apps(&self) -> Vec<String> {
+    if !self.enabled {
+      return Vec::new();
+    }
+    let mut running = self
+      .app
+      .state::<AppState>()
+      .running_apps
+      .lock()
+      .map(|running| running.clone())
+      .unwrap_or_default();
+    running.sort();
+    running.dedup();
+    running
+  }
+
+  fn set_deferred(&mut self, running: &[String], action: &str) {
+    let deferred = !running.is_empty();
+    if deferred == self.deferred {
+      return;
+    }
+    self.deferred = deferred;
+    if deferred {
+      let _ = self.app.emit_all(
+        "work-deferred",
+        serde_json::json!({
+          "appId": self.app_id,
+          "action": action,
+          "runningApps": running,
+          "reason": format!("{} while {} is running.", action, running.join(", ")),
+        }),
+      );
+    } else {
+      let _ = self
+        .app
+        .emit_all("work-resumed", serde_json::json!({ "appId": self.app_id }));
+    }
+  }
+
+  /// Call from a download progress callback with the bytes copied so far;
+  /// sleeps as needed to keep the rate under `DEFERRED_DOWNLOAD_BYTES_PER_SEC`.
+  fn throttle(&mut self, copied: u64) {
+    let previous = self.last_bytes.replace(copied).unwrap_or(copied);
+    let running = self.running_apps();
+    self.set_deferred(&running, "Download throttled");
+    if self.deferred {
+      let budget = Duration::from_secs_f64(
+        copied.saturating_sub(previous) as f64 / DEFERRED_DOWNLOAD_BYTES_PER_SEC as f64,
+      );
+      if let Some(remaining) = budget.checked_sub(self.last_check.elapsed()) {
+        std::thread::sleep(remaining);
+      }
+    }
+    self.last_check = Instant::now();
+  }
+
+  fn take_cancel(&self) -> bool {
+    self
+      .app
+      .state::<AppState>()
+      .cancelled_deferrals
+      .lock()
+      .map(|mut cancelled| cancelled.remove(self.app_id))
+      .unwrap_or(false)
+  }
+
+  /// Blocks until no launched app is running. Fails when the user cancels
+  /// through `cancel_deferred_work` or after `DEFERRED_WAIT_LIMIT`.
+  fn wait(&mut self) -> Result<(), String> {
+    let started = Instant::now();
+    loop {
+      let running = self.running_apps();
+      if running.is_empty() {
+        self.set_deferred(&running, "Install paused");
+        return Ok(());
+      }
+      if self.take_cancel() {
+        self.set_deferred(&[], "Install paused");
+        return Err("Install cancelled.".to_string());
+      }
+      if started.elapsed() >= DEFERRED_WAIT_LIMIT {
+        self.set_deferred(&[], "Install paused");
+        return Err(format!(
+          "Gave up waiting for {} to close. Try the install again later.",
+          running.join(", ")
+        ));
+      }
+      self.set_deferred(&running, "Install paused");
+      std::thread::sleep(DEFERRED_POLL_INTERVAL);
+    }
+  }
+}
+
+/// Cancels the install of `app_id` that is paused while launched apps run.
+#[tauri::command]
+fn cancel_deferred_work(app_id: String, state: tauri::State<AppState>) {
+  if let Ok(mut cancelled) = state.cancelled_deferrals.lock() {
+    cancelled.insert(app_id);
+  }
+}
+
+#[tauri::command]
+fn launch_path(path: String) -> Result<(), String> {
+  let target = PathBuf::from(&path);
+  if !target.exists() {
+    return Err("File not found.".to_string());
+  }
+  std::process::Command::new(target)
+    .spawn()
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LaunchPriority {
+  Low,
+  Normal,
+  High,
+}
+
+impl LaunchPriority {
+  fn parse(value: &str) -> Option<Self> {
+    match value.trim().to_ascii_lowercase().as_str() {
+      "low" => Some(LaunchPriority::Low),
+      "normal" => Some(LaunchPriority::Normal),
+      "high" => Some(LaunchPriority::High),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn apply_process_priority(child: &Child, priority: LaunchPriority) -> Result<(), String> {
+  use std::os::windows::io::AsRawHandle;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn SetPriorityClass(process: *mut std::ffi::c_void, priority_class: u32) -> i32;
+  }
+  const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+  const NORMAL_PRIORITY_CLASS: u32 = 0x0000_0020;
+  const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+
+  let class = match priority {
+    LaunchPriority::Low => BELOW_NORMAL_PRIORITY_CLASS,
+    LaunchPriority::Normal => NORMAL_PRIORITY_CLASS,
+    LaunchPriority::High => ABOVE_NORMAL_PRIORITY_CLASS,
+  };
+  // SAFETY: the handle belongs to a child we spawned and still own.
+  if unsafe { SetPriorityClass(child.as_raw_handle() as _, class) } == 0 {
+    return Err(std::io::Error::last_os_error().to_string());
+  }
+  Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn apply_process_priority(child: &Child, priority: LaunchPriority) -> Result<(), String> {
+  let niceness = match priority {
+    LaunchPriority::Low => "10",
+    LaunchPriority::Normal => "0",
+    // Raising priority needs privileges on most systems; failures are only logged.
+    LaunchPriority::High => "-5",
+  };
+  let status = std::process::Command::new("renice")
+    .args(["-n", niceness, "-p", &child.id().to_string()])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("renice exited with code {:?}.", status.code()))
+  }
+}
+
+/// Per-app launch settings. Every field is optional so a partial profile can
+/// be layered over another one.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchProfile {
+  args: Option<Vec<String>>,
+  env: Option<HashMap<String, String>>,
+  working_dir: Option<String>,
+  priority: Option<String>,
+}
+
+impl LaunchProfile {
+  /// Fields set on `self` win; the rest come from `base`.
+  fn merged_over(self, base: LaunchProfile) -> LaunchProfile {
+    LaunchProfile {
+      args: self.args.or(base.args),
+      env: self.env.or(base.env),
+      working_dir: self.working_dir.or(base.working_dir),
+      priority: self.priority.or(base.priority),
+    }
+  }
+}
+
+fn launch_profiles_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("launch-profiles.json"))
+}
+
+fn load_launch_profiles() -> HashMap<String, LaunchProfile> {
+  launch_profiles_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+#[tauri::command]
+fn get_launch_profile(app_id: String) -> LaunchProfile {
+  load_launch_profiles().remove(&app_id).unwrap_or_default()
+}
+
+#[tauri::command]
+fn set_launch_profile(app_id: String, profile: LaunchProfile) -> Result<(), String> {
+  let path = launch_profiles_path().ok_or_else(|| "Missing app data dir.".to_string())?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let mut profiles = load_launch_profiles();
+  profiles.insert(app_id, profile);
+  let data = serde_json::to_vec_pretty(&profiles).map_err(|e| e.to_string())?;
+  std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+const DEFAULT_READINESS_TIMEOUT_MS: u64 = 30_000;
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What has to be true before a launched app counts as ready. Every check
+/// that is set must pass.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ReadinessCheck {
+  /// A localhost TCP port that accepts connections.
+  port: Option<u16>,
+  /// A file that exists.
+  file: Option<String>,
+  /// Text that appears in a line of the app's stdout.
+  log_pattern: Option<String>,
+  timeout_ms: Option<u64>,
+}
+
+/// Polls `check` on a background thread and emits `app-ready` once it passes,
+/// or `app-ready-timeout` when the timeout runs out or the app exits first.
+/// `stdout` is drained for as long as the app runs so it never blocks.
+fn watch_app_readiness(
+  app: tauri::AppHandle,
+  app_id: String,
+  check: ReadinessCheck,
+  stdout: Option<std::process::ChildStdout>,
+) {
+  let started = Instant::now();
+  let timeout = Duration::from_millis(check.timeout_ms.unwrap_or(DEFAULT_READINESS_TIMEOUT_MS));
+  let log_events = match (stdout, check.log_pattern.clone()) {
+    (Some(stdout), Some(pattern)) => {
+      let (sender, receiver) = std::sync::mpsc::channel();
+      std::thread::spawn(move || {
+        let mut matched = false;
+        for line in BufReader::new(stdout).lines() {
+          let Ok(line) = line else {
+            break;
+          };
+          if !matched && line.contains(&pattern) {
+            matched = true;
+            let _ = sender.send(true);
+          }
+        }
+        let _ = sender.send(false);
+      });
+      Some(receiver)
+    }
+    _ => None,
+  };
+  std::thread::spawn(move || {
+    let mut log_matched = check.log_pattern.is_none();
+    let mut output_closed = false;
+    loop {
+      if let Some(receiver) = &log_events {
+        while let Ok(matched) = receiver.try_recv() {
+          log_matched |= matched;
+          output_closed |= !matched;
+        }
+      }
+      let port_open = check.port.is_none_or(|port| {
+        let address = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+        std::net::TcpStream::connect_timeout(&address, READINESS_POLL_INTERVAL).is_ok()
+      });
+      let file_present = check
+        .file
+        .as_deref()
+        .is_none_or(|file| Path::new(file).exists());
+      if log_matched && port_open && file_present {
+        let _ = app.emit_all(
+          "app-ready",
+          serde_json::json!({
+            "appId": app_id,
+            "elapsedMs": started.elapsed().as_millis() as u64,
+          }),
+        );
+        return;
+      }
+      let exited = output_closed
+        || app
+          .state::<AppState>()
+          .running_apps
+          .lock()
+          .map(|running| !running.contains(&app_id))
+          .unwrap_or(false);
+      if exited || started.elapsed() >= timeout {
+        let _ = app.emit_all(
+          "app-ready-timeout",
+          serde_json::json!({
+            "appId": app_id,
+            "elapsedMs": started.elapsed().as_millis() as u64,
+            "exited": exited,
+          }),
+        );
+        return;
+      }
+      std::thread::sleep(READINESS_POLL_INTERVAL);
+    }
+  });
+}
+
+/// Launches an installed app with its saved launch profile and tracks it until
+/// it exits (see `get_last_exit`). `override_profile` is merged over the saved
+/// profile for this launch only and is never persisted. `priority` is "low",
+/// "normal" or "high" and takes precedence over the profile's; an unknown
+/// value or a failure to apply it is logged and the app keeps its default
+/// priority. With `readiness` set, `app-ready` or `app-ready-timeout` follows
+/// once the app is up (see `watch_app_readiness`).
+#[tauri::command]
+fn launch_app(
+  window: tauri::Window,
+  app_id: String,
+  path: String,
+  priority: Option<String>,
+  override_profile: Option<LaunchProfile>,
+  readiness: Option<ReadinessCheck>,
+) -> Result<(), String> {
+  let target = PathBuf::from(&path);
+  if !target.exists() {
+    return Err(missing_app_file_error(&app_id, &target));
+  }
+  let saved = load_launch_profiles().remove(&app_id).unwrap_or_default();
+  let profile = override_profile.unwrap_or_default().merged_over(saved);
+  let priority = priority.or(profile.priority);
+
+  let mut command = std::process::Command::new(target);
+  if let Some(args) = &profile.args {
+    command.args(args);
+  }
+  if let Some(env) = &profile.env {
+    command.envs(env);
+  }
+  if let Some(dir) = profile
+    .working_dir
+    .as_deref()
+    .filter(|dir| !dir.trim().is_empty())
+  {
+    command.current_dir(dir);
+  }
+  if readiness
+    .as_ref()
+    .is_some_and(|check| check.log_pattern.is_some())
+  {
+    command.stdout(Stdio::piped());
+  }
+  let mut child = command
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|e| e.to_string())?;
+
+  if let Some(value) = priority.as_deref() {
+    match LaunchPriority::parse(value) {
+      Some(LaunchPriority::Normal) => {}
+      Some(priority) => {
+        if let Err(error) = apply_process_priority(&child, priority) {
+          write_log(
+            "warning",
+            &format!("could not set {} priority for {}: {}", value, app_id, error),
+          );
+        }
+      }
+      None => write_log(
+        "warning",
+        &format!(
+          "ignoring unknown launch priority {:?} for {}",
+          value, app_id
+        ),
+      ),
+    }
+  }
+
+  record_launch(&app_id);
+  let stdout = child.stdout.take();
+  track_app_exit(window.app_handle(), app_id.clone(), child);
+  if let Some(check) = readiness {
+    watch_app_readiness(window.app_handle(), app_id, check, stdout);
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn get_last_exit(app_id: String, state: tauri::State<AppState>) -> Option<AppExitInfo> {
+  state
+    .exits
+    .lock()
+    .ok()
+    .and_then(|exits| exits.get(&app_id).cloned())
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+struct LaunchStats {
+  launches: u64,
+  last_launched_at: Option<u64>,
+  total_runtime_ms: u64,
+}
+
+/// Serializes read-modify-write updates of the launch stats file, which the
+/// exit-tracking threads update concurrently.
+static LAUNCH_STATS_LOCK: Mutex<()> = Mutex::new(());
+
+fn launch_stats_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("launch-stats.json"))
+}
+
+fn load_launch_stats() -> HashMap<String, LaunchStats> {
+  launch_stats_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn update_launch_stats(app_id: &str, update: impl FnOnce(&mut LaunchStats)) {
+  let _guard = LAUNCH_STATS_LOCK.lock();
+  let Some(path) = launch_stats_path() else {
+    return;
+  };
+  let mut stats = load_launch_stats();
+  update(stats.entry(app_id.to_string()).or_default());
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(&stats) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+fn record_launch(app_id: &str) {
+  update_launch_stats(app_id, |stats| {
+    stats.launches += 1;
+    stats.last_launched_at = Some(unix_timestamp());
+  });
+}
+
+fn record_app_runtime(app_id: &str, runtime_ms: u64) {
+  update_launch_stats(app_id, |stats| {
+    stats.total_runtime_ms = stats.total_runtime_ms.saturating_add(runtime_ms);
+  });
+}
+
+#[tauri::command]
+fn get_launch_stats() -> HashMap<String, LaunchStats> {
+  load_launch_stats()
+}
+
+const CORRUPT_INSTALLER_MESSAGE: &str = "Installer appears corrupt, please re-download.";
+const CFB_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+fn has_pe_header(file: &mut File) -> std::io::Result<bool> {
+  let mut header = [0u8; 64];
+  file.read_exact(&mut header)?;
+  if &header[..2] != b"MZ" {
+    return Ok(false);
+  }
+  let offset = u32::from_le_bytes([header[60], header[61], header[62], header[63]]);
+  file.seek(SeekFrom::Start(u64::from(offset)))?;
+  let mut signature = [0u8; 4];
+  file.read_exact(&mut signature)?;
+  Ok(&signature == b"PE\0\0")
+}
+
+fn has_cfb_header(file: &mut File) -> std::io::Result<bool> {
+  let mut signature = [0u8; 8];
+  file.read_exact(&mut signature)?;
+  Ok(signature == CFB_SIGNATURE)
+}
+
+/// Values accepted for the `installer_type` preference.
+const KNOWN_INSTALLER_TYPES: &[&str] = &["msi", "exe", "inno", "nsis", "zip", "msix"];
+/// How much of an EXE is searched for an installer framework's marker.
+const INSTALLER_MARKER_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+/// How much of a ZIP's end (where its file list lives) is searched for an
+/// MSIX manifest.
+const ZIP_DIRECTORY_SCAN_BYTES: u64 = 64 * 1024;
+
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+  haystack
+    .windows(needle.len())
+    .any(|window| window == needle)
+}
+
+/// Identifies an installer from its contents rather than its name: MSI by its
+/// compound-file header, MSIX and ZIP by the ZIP header, and EXEs as Inno
+/// Setup or NSIS when their marker appears near the start.
+fn detect_installer_kind(path: &Path) -> Option<&'static str> {
+  let mut file = File::open(path).ok()?;
+  let mut magic = [0u8; 8];
+  file.read_exact(&mut magic).ok()?;
+  if magic == CFB_SIGNATURE {
+    return Some("msi");
+  }
+  if magic.starts_with(b"PK\x03\x04") {
+    let len = file.metadata().ok()?.len();
+    file
+      .seek(SeekFrom::Start(
+        len.saturating_sub(ZIP_DIRECTORY_SCAN_BYTES),
+      ))
+      .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    return Some(if contains_bytes(&tail, b"AppxManifest.xml") {
+      "msix"
+    } else {
+      "zip"
+    });
+  }
+  file.seek(SeekFrom::Start(0)).ok()?;
+  if !has_pe_header(&mut file).ok()? {
+    return None;
+  }
+  file.seek(SeekFrom::Start(0)).ok()?;
+  let mut head = Vec::new();
+  file
+    .take(INSTALLER_MARKER_SCAN_BYTES)
+    .read_to_end(&mut head)
+    .ok()?;
+  if contains_bytes(&head, b"Inno Setup") {
+    Some("inno")
+  } else if contains_bytes(&head, b"Nullsoft") {
+    Some("nsis")
+  } else {
+    Some("exe")
+  }
+}
+
+#[tauri::command]
+fn detect_installer_type(path: String) -> Option<String> {
+  detect_installer_kind(Path::new(&path)).map(str::to_string)
+}
+
+/// Inno Setup and NSIS installers are EXEs too, so a generic "exe" on either
+/// side is not a disagreement.
+fn installer_types_agree(preferred: &str, detected: &str) -> bool {
+  let is_exe = |kind: &str| matches!(kind, "exe" | "inno" | "nsis");
+  preferred == detected
+    || (preferred == "exe" && is_exe(detected))
+    || (detected == "exe" && is_exe(preferred))
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallerTypeMismatch {
+  app_id: Option<String>,
+  path: String,
+  preferred: String,
+  detected: String,
+}
+
+/// Returns the installer type to use for `path`: the detected one when
+/// detection succeeds, otherwise the `installer_type` preference. When the
+/// two disagree, emits `installer-type-mismatch` so the stale preference can
+/// be fixed.
+fn resolve_installer_type(
+  app: &tauri::AppHandle,
+  app_id: Option<&str>,
+  path: &Path,
+) -> Option<String> {
+  let preferred = current_preferences(&app.state::<AppState>()).installer_type;
+  let Some(detected) = detect_installer_kind(path) else {
+    return preferred;
+  };
+  if let Some(preferred) = preferred {
+    if !installer_types_agree(&preferred, detected) {
+      let _ = app.emit_all(
+        "installer-type-mismatch",
+        InstallerTypeMismatch {
+          app_id: app_id.map(str::to_string),
+          path: path.to_string_lossy().to_string(),
+          preferred,
+          detected: detected.to_string(),
+        },
+      );
+    }
+  }
+  Some(detected.to_string())
+}
+
+/// Stops an MSI install early when the file turns out to be another kind of
+/// installer.
+fn ensure_msi_installer(app: &tauri::AppHandle, app_id: &str, path: &Path) -> Result<(), String> {
+  match resolve_installer_type(app, Some(app_id), path) {
+    Some(kind) if kind != "msi" => Err(format!(
+      "{} is not an MSI package (it looks like a {} installer).",
+      path.file_name().unwrap_or_default().to_string_lossy(),
+      kind
+    )),
+    _ => Ok(()),
+  }
+}
+
+/// Rejects empty files and, for EXE/MSI, files whose headers are not what
+/// Windows expects, so a truncated download fails with a clear message.
+fn check_installer_integrity(path: &Path) -> Result<(), String> {
+  let metadata = std::fs::metadata(path).map_err(|_| "Installer not found.".to_string())?;
+  if metadata.len() == 0 {
+    return Err(CORRUPT_INSTALLER_MESSAGE.to_string());
+  }
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| ext.to_ascii_lowercase());
+  let mut file = File::open(path).map_err(|e| e.to_string())?;
+  let valid = match extension.as_deref() {
+    Some("exe") => has_pe_header(&mut file),
+    Some("msi") => has_cfb_header(&mut file),
+    _ => Ok(true),
+  };
+  match valid {
+    Ok(true) => Ok(()),
+    Ok(false) => Err(CORRUPT_INSTALLER_MESSAGE.to_string()),
+    Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+      Err(CORRUPT_INSTALLER_MESSAGE.to_string())
+    }
+    Err(err) => Err(err.to_string()),
+  }
+}
+
+/// Only Windows has a scanner to ask, so elsewhere every scan is an `Error`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+enum ScanVerdict {
+  Clean,
+  Infected,
+  Error,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanReport {
+  verdict: ScanVerdict,
+  detail: Option<String>,
+}
+
+impl ScanReport {
+  fn error(detail: impl Into<String>) -> Self {
+    ScanReport {
+      verdict: ScanVerdict::Error,
+      detail: Some(detail.into()),
+    }
+  }
+}
+
+/// Scans a single file with Windows Defender's command-line scanner.
+/// Remediation is disabled so a flagged installer is reported rather than
+/// quarantined out from under the caller.
+#[cfg(target_os = "windows")]
+fn scan_with_defender(path: &Path) -> ScanReport {
+  let program_files =
+    std::env::var("ProgramFiles").unwrap_or_else(|_| r"C:\Program Files".to_string());
+  let scanner = PathBuf::from(program_files)
+    .join("Windows Defender")
+    .join("MpCmdRun.exe");
+  if !scanner.exists() {
+    return ScanReport::error("Windows Defender is not installed.");
+  }
+  let output = match std::process::Command::new(&scanner)
+    .args(["-Scan", "-ScanType", "3", "-DisableRemediation", "-File"])
+    .arg(path)
+    .output()
+  {
+    Ok(output) => output,
+    Err(err) => return ScanReport::error(err.to_string()),
+  };
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let last_line = || {
+    stdout
+      .lines()
+      .map(str::trim)
+      .rfind(|line| !line.is_empty())
+      .map(str::to_string)
+  };
+  // MpCmdRun exits 0 when nothing was found and 2 when a threat was detected;
+  // anything else (Defender disabled, scan engine unavailable) is an error.
+  match output.status.code() {
+    Some(0) => ScanReport {
+      verdict: ScanVerdict::Clean,
+      detail: None,
+    },
+    Some(2) => ScanReport {
+      verdict: ScanVerdict::Infected,
+      detail: stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("Threat"))
+        .map(str::to_string)
+        .or_else(last_line),
+    },
+    code => ScanReport::error(
+      last_line().unwrap_or_else(|| format!("Windows Defender exited with code {:?}.", code)),
+    ),
+  }
+}
+
+fn scan_path(path: &Path) -> ScanReport {
+  if !path.exists() {
+    return ScanReport::error("File not found.");
+  }
+  #[cfg(target_os = "windows")]
+  {
+    scan_with_defender(path)
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    ScanReport::error("Antivirus scanning is only supported on Windows.")
+  }
+}
+
+/// Runs the pre-install scan when `scan_downloads` is on. An installer that
+/// could not be scanned is not run either; turning the setting off is how
+/// the user chooses to install without a scan.
+fn scan_before_install(prefs: &HubPreferences, installer: &Path) -> Result<(), String> {
+  if !prefs.scan_downloads {
+    return Ok(());
+  }
+  let report = scan_path(installer);
+  match report.verdict {
+    ScanVerdict::Clean => Ok(()),
+    ScanVerdict::Infected => Err(format!(
+      "Windows Defender flagged this installer{}. It was not run.",
+      report
+        .detail
+        .map(|detail| format!(" ({})", detail))
+        .unwrap_or_default()
+    )),
+    ScanVerdict::Error => Err(format!(
+      "This installer could not be scanned{}. It was not run; turn off download scanning to install it anyway.",
+      report
+        .detail
+        .map(|detail| format!(" ({})", detail))
+        .unwrap_or_default()
+    )),
+  }
+}
+
+#[tauri::command]
+fn scan_file(path: String) -> ScanReport {
+  scan_path(Path::new(&path))
+}
+
+/// The folder an installer runs in: `working_dir` when given, otherwise the
+/// installer's own folder.
+fn installer_working_dir(target: &Path, working_dir: Option<&str>) -> Result<PathBuf, String> {
+  let working_dir = match working_dir.filter(|dir| !dir.is_empty()) {
+    Some(dir) => PathBuf::from(dir),
+    None => target
+      .parent()
+      .filter(|parent| !parent.as_os_str().is_empty())
+      .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+  };
+  if !working_dir.is_dir() {
+    return Err(format!(
+      "Working folder {} does not exist.",
+      working_dir.display()
+    ));
+  }
+  Ok(working_dir)
+}
+
+/// Starts `target` (through msiexec for an MSI) with `args` in `working_dir`.
+fn installer_command(
+  target: &Path,
+  is_msi: bool,
+  args: &[String],
+  working_dir: &Path,
+) -> std::process::Command {
+  // A relative path would be resolved against the new working folder.
+  let program = canonicalize_lenient(target);
+  let mut command = if is_msi {
+    let mut command = std::process::Command::new("msiexec");
+    command.arg("/i").arg(&program);
+    command
+  } else {
+    std::process::Command::new(&program)
+  };
+  command.args(args).current_dir(working_dir);
+  command
+}
+
+/// Runs a downloaded installer and waits for it. It starts in its own folder,
+/// where installers look for files they ship alongside, unless `working_dir`
+/// names another.
+#[tauri::command]
+fn run_installer(
+  app: tauri::AppHandle,
+  path: String,
+  args: Vec<String>,
+  app_id: Option<String>,
+  install_dir: Option<String>,
+  working_dir: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<(), String> {
+  let target = PathBuf::from(&path);
+  if !target.exists() {
+    return Err("Installer not found.".to_string());
+  }
+  let working_dir = installer_working_dir(&target, working_dir.as_deref())?;
+  check_installer_integrity(&target)?;
+  scan_before_install(&current_preferences(&state), &target)?;
+  ensure_installer_unchanged(&target)?;
+  ensure_bitness_fits(
+    &app,
+    app_id.as_deref(),
+    &target,
+    install_dir
+      .as_deref()
+      .filter(|dir| !dir.is_empty())
+      .map(Path::new),
+  )?;
+  let is_msi = resolve_installer_type(&app, app_id.as_deref(), &target).as_deref() == Some("msi");
+  let status = installer_command(&target, is_msi, &args, &working_dir)
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    if let Some(app_id) = app_id {
+      let silent_args = args
+        .iter()
+        .map(|arg| match install_dir.as_deref() {
+          Some(dir) if !dir.is_empty() => arg.replace(dir, INSTALL_DIR_PLACEHOLDER),
+          _ => arg.clone(),
+        })
+        .collect();
+      record_installer_type(
+        &app_id,
+        install_dir.as_deref(),
+        InstallerRecord {
+          installer_type: if is_msi { "msi" } else { "exe" }.to_string(),
+          silent_args,
+        },
+      );
+    }
+    Ok(())
+  } else {
+    let error = format!("Installer exited with code {:?}.", status.code());
+    if let Some(app_id) = app_id.as_deref() {
+      report_failure(
+        &state,
+        "install",
+        app_id,
+        Some(if is_msi { "msi" } else { "exe" }),
+        &error,
+      );
+    }
+    Err(error)
+  }
+}
+
+/// Characters a shell (`cmd.exe` in particular) gives special meaning to.
+const SHELL_METACHARACTERS: &[char] = &[
+  '&', '|', '<', '>', '^', '%', '!', '"', '`', '$', ';', '(', ')', '\n', '\r',
+];
+
+/// Rejects arguments a shell could interpret as more than plain text.
+fn validate_command_args(args: &[String]) -> Result<(), String> {
+  match args
+    .iter()
+    .find(|arg| arg.contains(SHELL_METACHARACTERS) || arg.contains('\0'))
+  {
+    Some(arg) => Err(format!(
+      "Argument {:?} contains shell metacharacters; pass shell: true to allow it.",
+      arg
+    )),
+    None => Ok(()),
+  }
+}
+
+/// Looks `program` up on PATH the way `cmd` would, trying each PATHEXT
+/// extension, so it can be started without going through the shell.
+#[cfg(target_os = "windows")]
+fn find_on_path(program: &str, cwd: &Path) -> Option<PathBuf> {
+  let candidate = Path::new(program);
+  let extensions: Vec<String> = std::env::var("PATHEXT")
+    .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+    .split(';')
+    .filter(|ext| !ext.is_empty())
+    .map(str::to_string)
+    .collect();
+  let with_extensions = |base: PathBuf| {
+    if base.extension().is_some() && base.is_file() {
+      return Some(base);
+    }
+    extensions.iter().find_map(|ext| {
+      let path = PathBuf::from(format!("{}{}", base.display(), ext));
+      path.is_file().then_some(path)
+    })
+  };
+  if candidate.components().count() > 1 || candidate.has_root() {
+    return with_extensions(cwd.join(candidate));
+  }
+  let path_var = std::env::var_os("PATH")?;
+  std::iter::once(cwd.to_path_buf())
+    .chain(std::env::split_paths(&path_var))
+    .find_map(|dir| with_extensions(dir.join(program)))
+}
+
+#[tauri::command]
+fn run_dev_app(cwd: String, command: Vec<String>, shell: Option<bool>) -> Result<(), String> {
+  if command.is_empty() {
+    return Err("Missing dev command.".to_string());
+  }
+  let shell = shell.unwrap_or(false);
+  if !shell {
+    validate_command_args(&command)?;
+  }
+  #[cfg(target_os = "windows")]
+  let mut cmd = {
+    let program = if shell {
+      None
+    } else {
+      find_on_path(&command[0], Path::new(&cwd))
+    };
+    let is_binary = program.as_ref().is_some_and(|path| {
+      path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("exe") || ext.eq_ignore_ascii_case("com"))
+    });
+    match program {
+      Some(program) if is_binary => {
+        let mut cmd = std::process::Command::new(program);
+        cmd.args(&command[1..]);
+        cmd
+      }
+      // Batch scripts (pnpm.cmd, npm.cmd) only run through cmd; without
+      // `shell` their arguments were already checked for metacharacters.
+      _ => {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.arg("/c");
+        cmd.args(&command);
+        cmd
+      }
+    }
+  };
+  #[cfg(not(target_os = "windows"))]
+  let mut cmd = if shell {
+    let mut cmd = std::process::Command::new("sh");
+    cmd.arg("-c").arg(command.join(" "));
+    cmd
+  } else {
+    let mut cmd = std::process::Command::new(&command[0]);
+    if command.len() > 1 {
+      cmd.args(&command[1..]);
+    }
+    cmd
+  };
+  cmd.current_dir(cwd);
+  cmd.spawn().map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+/// Confirms `exe_path` resolves to somewhere inside `install_path`, so a
+/// shortcut can never be pointed outside the app's folder.
+fn ensure_inside_install_dir(install_path: &Path, exe_path: &Path) -> Result<PathBuf, String> {
+  let outside = || format!("{} is outside the install folder.", exe_path.display());
+  if exe_path
+    .components()
+    .any(|component| matches!(component, std::path::Component::ParentDir))
+  {
+    return Err(outside());
+  }
+  let install_path = canonicalize_lenient(install_path);
+  let exe_path = canonicalize_lenient(exe_path);
+  if exe_path != install_path && exe_path.starts_with(&install_path) {
+    Ok(exe_path)
+  } else {
+    Err(outside())
+  }
+}
+
+/// Joins a relative `exe_name` onto `install_path`, rejecting absolute names,
+/// `..` traversal and anything else that would land outside the folder.
+fn resolve_install_exe(install_path: &Path, exe_name: &str) -> Result<PathBuf, String> {
+  let name = Path::new(exe_name);
+  if exe_name.trim().is_empty() || name.has_root() || name.is_absolute() {
+    return Err(format!(
+      "{} is not a path inside the install folder.",
+      exe_name
+    ));
+  }
+  ensure_inside_install_dir(install_path, &install_path.join(name))
+}
+
+#[tauri::command]
+fn validate_exe_path(install_dir: String, exe_name: String) -> Result<String, String> {
+  resolve_install_exe(Path::new(&install_dir), &exe_name)
+    .map(|path| path.to_string_lossy().to_string())
+}
+
+/// Device names Windows will not accept as a file name, with any extension.
+const RESERVED_FILE_NAMES: &[&str] = &[
+  "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+  "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns an app name into the `.lnk` file name its shortcuts use. Characters
+/// Windows forbids in file names become `_`, surrounding spaces and trailing
+/// dots are trimmed and device names get a `_` after the name, before any
+/// extension (`CON.txt` becomes `CON_.txt`). Creating and removing
+/// shortcuts both go through this so they always agree. `None` when nothing
+/// usable is left.
+fn shortcut_file_name(app_name: &str) -> Option<String> {
+  let cleaned: String = app_name
+    .chars()
+    .map(|c| {
+      if c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') {
+        '_'
+      } else {
+        c
+      }
+    })
+    .collect();
+  let mut name = cleaned.trim().trim_end_matches(['.', ' ']).to_string();
+  if name.is_empty() {
+    return None;
+  }
+  let stem = name.split('.').next().unwrap_or_default().trim_end();
+  if RESERVED_FILE_NAMES.contains(&stem.to_ascii_uppercase().as_str()) {
+    // Windows checks the part before the extension, so the mark goes there.
+    name.insert(stem.len(), '_');
+  }
+  Some(format!("{}.lnk", name))
+}
+
+fn required_shortcut_file_name(app_name: &str) -> Result<String, String> {
+  shortcut_file_name(app_name)
+    .ok_or_else(|| format!("\"{}\" cannot be used as a shortcut name.", app_name))
+}
+
+/// Returns the file name shortcuts for `app_name` are created under.
+#[tauri::command]
+fn normalize_shortcut_name(app_name: String) -> Result<String, String> {
+  required_shortcut_file_name(&app_name)
+}
+
+/// Creates shortcuts to `exe_path`. When `install_dir` is given the executable
+/// must live inside it.
+#[tauri::command]
+fn create_shortcuts(
+  exe_path: String,
+  app_name: String,
+  create_desktop_shortcut: bool,
+  create_start_menu_shortcut: bool,
+  install_dir: Option<String>,
+) -> Result<(), String> {
+  let mut target = PathBuf::from(&exe_path);
+  if let Some(install_dir) = install_dir.as_deref() {
+    target = ensure_inside_install_dir(Path::new(install_dir), &target)?;
+  }
+  if !target.exists() {
+    return Err("Executable not found.".to_string());
+  }
+  let working_dir = target
+    .parent()
+    .ok_or_else(|| "Executable directory missing.".to_string())?;
+
+  let file_name = required_shortcut_file_name(&app_name)?;
+  if create_desktop_shortcut {
+    if let Some(desktop) = tauri::api::path::desktop_dir() {
+      let shortcut = desktop.join(&file_name);
+      create_shortcut(&shortcut, &target, working_dir)?;
+    }
+  }
+
+  if create_start_menu_shortcut {
+    // Like a missing desktop, a missing Start menu only skips its shortcut.
+    match ensure_start_menu_dir() {
+      Ok(dir) => create_shortcut(&dir.join(&file_name), &target, working_dir)?,
+      Err(err) => write_log(
+        "warning",
+        &format!("skipped the Start menu shortcut: {}", err),
+      ),
+    }
+  }
+
+  Ok(())
+}
+
+/// Tries per file before a locked file is left for the next reboot.
+const LOCKED_FILE_ATTEMPTS: u32 = 3;
+const LOCKED_FILE_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RemovalSummary {
+  /// Files and folders deleted right away.
+  removed: usize,
+  /// Paths still in use that Windows will delete on the next restart.
+  scheduled: Vec<String>,
+  /// Paths that could neither be deleted nor scheduled.
+  failed: Vec<String>,
+  reboot_required: bool,
+}
+
+/// Asks Windows to delete `path` during the next restart. This needs write
+/// access to HKLM, so it fails without administrator rights.
+#[cfg(target_os = "windows")]
+fn delete_on_reboot(path: &Path) -> bool {
+  use std::os::windows::ffi::OsStrExt;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn MoveFileExW(existing: *const u16, new: *const u16, flags: u32) -> i32;
+  }
+  const MOVEFILE_DELAY_UNTIL_REBOOT: u32 = 0x4;
+
+  let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+  // SAFETY: `wide` is NUL-terminated and a null destination means delete.
+  unsafe { MoveFileExW(wide.as_ptr(), std::ptr::null(), MOVEFILE_DELAY_UNTIL_REBOOT) != 0 }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn delete_on_reboot(_path: &Path) -> bool {
+  false
+}
+
+/// Deletes `path` (a file, or a folder with `remove`), retrying while it is
+/// locked and scheduling it for deletion on reboot when it stays locked.
+fn remove_with_retry(
+  path: &Path,
+  remove: fn(&Path) -> std::io::Result<()>,
+  summary: &mut RemovalSummary,
+) {
+  for attempt in 1..=LOCKED_FILE_ATTEMPTS {
+    match remove(path) {
+      Ok(()) => {
+        summary.removed += 1;
+        return;
+      }
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+      Err(_) if attempt < LOCKED_FILE_ATTEMPTS => std::thread::sleep(LOCKED_FILE_RETRY_DELAY),
+      Err(_) => {}
+    }
+  }
+  let display = path.to_string_lossy().to_string();
+  if delete_on_reboot(path) {
+    summary.scheduled.push(display);
+    summary.reboot_required = true;
+  } else {
+    summary.failed.push(display);
+  }
+}
+
+/// Removes a folder tree file by file so one locked file does not stop the
+/// rest from going. Symlinks and junctions are removed, never followed.
+/// Folders whose contents were scheduled are scheduled too, after them, so
+/// they go on the same restart.
+fn remove_tree(path: &Path, summary: &mut RemovalSummary) {
+  let Ok(metadata) = std::fs::symlink_metadata(path) else {
+    return;
+  };
+  if metadata.file_type().is_symlink() {
+    // Windows removes links to folders (and junctions) as folders.
+    remove_with_retry(
+      path,
+      |path| std::fs::remove_file(path).or_else(|_| std::fs::remove_dir(path)),
+      summary,
+    );
+    return;
+  }
+  if !metadata.is_dir() {
+    remove_with_retry(path, |path| std::fs::remove_file(path), summary);
+    return;
+  }
+  if let Ok(entries) = std::fs::read_dir(path) {
+    for entry in entries.flatten() {
+      remove_tree(&entry.path(), summary);
+    }
+  }
+  remove_with_retry(path, |path| std::fs::remove_dir(path), summary);
+}
+
+/// Removes `install_path` and reports what is left for a reboot. Fails only
+/// when something could not be removed or scheduled.
+fn remove_install_dir(install_path: &Path) -> Result<RemovalSummary, String> {
+  let mut summary = RemovalSummary::default();
+  remove_tree(install_path, &mut summary);
+  match summary.failed.first() {
+    Some(first) => Err(format!(
+      "{} item(s) are in use and could not be removed (for example {}). Close the app and try again.",
+      summary.failed.len(),
+      first
+    )),
+    None => Ok(summary),
+  }
+}
+
+/// Removes an installed app's folder and shortcuts. When `app_id` is given, its
+/// Apps & features entry and install manifest are removed as well. Files that
+/// are still in use are scheduled for deletion on the next restart.
+#[tauri::command]
+fn uninstall_app(
+  install_dir: String,
+  app_name: String,
+  app_id: Option<String>,
+) -> Result<RemovalSummary, String> {
+  let install_path = PathBuf::from(&install_dir);
+  let summary = remove_install_dir(&install_path)?;
+
+  if let Some(file_name) = shortcut_file_name(&app_name) {
+    if let Some(desktop) = tauri::api::path::desktop_dir() {
+      let shortcut = desktop.join(&file_name);
+      if shortcut.exists() {
+        let _ = std::fs::remove_file(shortcut);
+      }
+    }
+
+    if let Some(start_menu) = start_menu_dir() {
+      let start_menu = start_menu.join(&file_name);
+      if start_menu.exists() {
+        let _ = std::fs::remove_file(start_menu);
+      }
+    }
+  }
+
+  if let Some(app_id) = app_id {
+    forget_install(&app_id);
+  }
+  Ok(summary)
+}
+
+/// Drops the registry entries and manifest recorded for an app.
+fn forget_install(app_id: &str) {
+  let Some(manifest_path) = install_manifest_path(app_id) else {
+    return;
+  };
+  #[cfg(target_os = "windows")]
+  if let Some(manifest) = load_install_manifest(app_id) {
+    for key in &manifest.registry_keys {
+      let _ = reg_delete(key);
+    }
+  }
+  let _ = std::fs::remove_file(manifest_path);
+}
+
+/// Uninstalls an app using only its install manifest, for the Apps & features
+/// "Uninstall" button.
+fn uninstall_from_manifest(app_id: &str) -> Result<(), String> {
+  let manifest = load_install_manifest(app_id)
+    .ok_or_else(|| format!("{} is not installed by the hub.", app_id))?;
+  let question = format!(
+    "Uninstall {}?\n\nThis deletes {} and the app's shortcuts.",
+    app_id, manifest.install_dir
+  );
+  if !confirm_uninstall(&question) {
+    return Ok(());
+  }
+  let summary = remove_install_dir(Path::new(&manifest.install_dir))?;
+  if summary.reboot_required {
+    write_log(
+      "warning",
+      &format!(
+        "{} item(s) are in use and will be removed after a restart.",
+        summary.scheduled.len()
+      ),
+    );
+  }
+  for shortcut in &manifest.shortcuts {
+    let _ = std::fs::remove_file(shortcut);
+  }
+  forget_install(app_id);
+  Ok(())
+}
+
+/// Asks before an uninstall started outside the hub deletes anything: with a
+/// message box on Windows, on the terminal elsewhere.
+fn confirm_uninstall(question: &str) -> bool {
+  #[cfg(target_os = "windows")]
+  {
+    message_box(question, MB_YESNO | MB_ICONQUESTION) == IDYES
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && answer.trim().eq_ignore_ascii_case("y")
+  }
+}
+
+/// Classic Windows path limit; deeper paths fail unless long path support is on.
+const MAX_PATH_CHARS: usize = 260;
+
+/// Picks the long name out of an MSI `short|long` name pair.
+fn msi_long_name(value: &str) -> &str {
+  value.rsplit('|').next().unwrap_or(value)
+}
+
+/// Walks the Directory table from `id` up to the root, returning the folder
+/// names below the root in order. Root and "." entries contribute nothing.
+fn msi_directory_segments(
+  directories: &HashMap<String, (Option<String>, String)>,
+  id: &str,
+) -> Vec<String> {
+  let mut segments = Vec::new();
+  let mut current = Some(id.to_string());
+  // Bounded so a malformed, cyclic table cannot loop forever.
+  for _ in 0..64 {
+    let Some((parent, name)) = current.as_ref().and_then(|id| directories.get(id)) else {
+      break;
+    };
+    if parent.is_some() && name != "." && !name.is_empty() {
+      segments.push(name.clone());
+    }
+    current = parent.clone();
+  }
+  segments.reverse();
+  segments
+}
+
+/// Predicts where each file in an MSI lands under `install_path`, following
+/// the source-directory layout the extractor writes.
+fn msi_payload_paths(installer: &Path, install_path: &Path) -> std::io::Result<Vec<PathBuf>> {
+  let mut package = msi::open(installer)?;
+
+  let mut directories = HashMap::new();
+  for row in package.select_rows(msi::Select::table("Directory"))? {
+    let id = row["Directory"].as_str().unwrap_or_default().to_string();
+    let parent = row["Directory_Parent"]
+      .as_str()
+      .filter(|parent| !parent.is_empty() && *parent != id)
+      .map(str::to_string);
+    let default_dir = row["DefaultDir"].as_str().unwrap_or(".");
+    let source = default_dir.rsplit(':').next().unwrap_or(default_dir);
+    directories.insert(id, (parent, msi_long_name(source).to_string()));
+  }
+
+  let mut components = HashMap::new();
+  for row in package.select_rows(msi::Select::table("Component"))? {
+    if let (Some(component), Some(directory)) =
+      (row["Component"].as_str(), row["Directory_"].as_str())
+    {
+      components.insert(component.to_string(), directory.to_string());
+    }
+  }
+
+  let mut paths = Vec::new();
+  for row in package.select_rows(msi::Select::table("File"))? {
+    let mut path = install_path.to_path_buf();
+    if let Some(directory) = row["Component_"]
+      .as_str()
+      .and_then(|component| components.get(component))
+    {
+      path.extend(msi_directory_segments(&directories, directory));
+    }
+    path.push(msi_long_name(row["FileName"].as_str().unwrap_or_default()));
+    paths.push(path);
+  }
+  Ok(paths)
+}
+
+/// Lists the payload paths that would exceed `MAX_PATH_CHARS` once extracted
+/// into `install_path`. Only Windows has the limit; elsewhere this is empty.
+/// An installer whose tables cannot be read is not flagged here; extraction
+/// reports that failure itself.
+fn overlong_extraction_paths(installer: &Path, install_path: &Path) -> Vec<String> {
+  if !cfg!(target_os = "windows") {
+    return Vec::new();
+  }
+  let install_path = canonicalize_lenient(install_path);
+  msi_payload_paths(installer, &install_path)
+    .unwrap_or_default()
+    .into_iter()
+    .map(|path| path.to_string_lossy().to_string())
+    .filter(|path| path.chars().count() >= MAX_PATH_CHARS)
+    .collect()
+}
+
+/// Checks before extraction that no file in the MSI would land on a path
+/// longer than Windows allows, returning the offending paths.
+#[tauri::command]
+fn validate_extraction_paths(installer_path: String, install_dir: String) -> Vec<String> {
+  overlong_extraction_paths(Path::new(&installer_path), Path::new(&install_dir))
+}
+
+/// Total size of the files in an MSI's File table.
+fn msi_payload_size(installer: &Path) -> std::io::Result<u64> {
+  let mut package = msi::open(installer)?;
+  let total = package
+    .select_rows(msi::Select::table("File"))?
+    .filter_map(|row| row["FileSize"].as_int())
+    .map(|size| size.max(0) as u64)
+    .sum();
+  Ok(total)
+}
+
+/// Lists the payload paths that would be written through a symlink or
+/// junction once extracted into `install_path`, or would land outside it.
+/// A linked install folder is reported on its own, since everything would
+/// go through it.
+fn linked_extraction_paths(installer: &Path, install_path: &Path) -> Vec<String> {
+  let paths = if is_link(install_path) {
+    Vec::new()
+  } else {
+    msi_payload_paths(installer, install_path).unwrap_or_default()
+  };
+  linked_payload_paths(&paths, install_path)
+}
+
+fn is_link(path: &Path) -> bool {
+  std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+/// The check behind `linked_extraction_paths`, for payload paths already
+/// joined onto `install_path`.
+fn linked_payload_paths(paths: &[PathBuf], install_path: &Path) -> Vec<String> {
+  if is_link(install_path) {
+    return vec![install_path.to_string_lossy().to_string()];
+  }
+  let mut checked = HashSet::new();
+  let mut linked = Vec::new();
+  for path in paths {
+    if path
+      .components()
+      .any(|part| part == std::path::Component::ParentDir)
+    {
+      linked.push(path.to_string_lossy().to_string());
+      continue;
+    }
+    for ancestor in path
+      .ancestors()
+      .take_while(|ancestor| *ancestor != install_path)
+    {
+      // Everything above an ancestor seen before was checked along with it.
+      if !checked.insert(ancestor.to_path_buf()) {
+        break;
+      }
+      if is_link(ancestor) {
+        linked.push(ancestor.to_string_lossy().to_string());
+      }
+    }
+  }
+  linked
+}
+
+/// Checks before extraction that nothing in the MSI would be written through
+/// a symlink or junction, returning the offending paths.
+#[tauri::command]
+fn validate_extraction_links(installer_path: String, install_dir: String) -> Vec<String> {
+  linked_extraction_paths(Path::new(&installer_path), Path::new(&install_dir))
+}
+
+/// Disk space taken by an install folder, in bytes.
+#[tauri::command]
+fn get_install_size(install_dir: String) -> u64 {
+  directory_size(Path::new(&install_dir))
+}
+
+/// Sums file sizes under `path` without following symlinks.
+fn directory_size(path: &Path) -> u64 {
+  let Ok(meta) = std::fs::symlink_metadata(path) else {
+    return 0;
+  };
+  if !meta.is_dir() {
+    return meta.len();
+  }
+  std::fs::read_dir(path)
+    .map(|entries| {
+      entries
+        .flatten()
+        .map(|entry| directory_size(&entry.path()))
+        .sum()
+    })
+    .unwrap_or(0)
+}
+
+fn bytes_per_second(bytes: u64, elapsed: Duration) -> u64 {
+  let seconds = elapsed.as_secs_f64();
+  if seconds > 0.0 {
+    (bytes as f64 / seconds) as u64
+  } else {
+    0
+  }
+}
+
+const EXTRACTION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Free memory needed to start extracting: cabinets are unpacked in memory, so
+/// large installers can exhaust RAM on small machines.
+const MIN_EXTRACTION_MEMORY: u64 = 256 * 1024 * 1024;
+
+/// Physical memory currently available to new allocations, where the OS
+/// reports it.
+fn available_memory_bytes() -> Option<u64> {
+  #[cfg(target_os = "windows")]
+  {
+    #[repr(C)]
+    struct MemoryStatusEx {
+      length: u32,
+      memory_load: u32,
+      total_phys: u64,
+      avail_phys: u64,
+      total_page_file: u64,
+      avail_page_file: u64,
+      total_virtual: u64,
+      avail_virtual: u64,
+      avail_extended_virtual: u64,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+      fn GlobalMemoryStatusEx(buffer: *mut MemoryStatusEx) -> i32;
+    }
+    // SAFETY: all-zero is a valid MemoryStatusEx; `length` is set as the API requires.
+    let mut status: MemoryStatusEx = unsafe { std::mem::zeroed() };
+    status.length = std::mem::size_of::<MemoryStatusEx>() as u32;
+    // SAFETY: `status` is a properly sized, writable MEMORYSTATUSEX.
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+      return None;
+    }
+    Some(status.avail_phys)
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    // "MemAvailable:   123456 kB"
+    std::fs::read_to_string("/proc/meminfo")
+      .ok()?
+      .lines()
+      .find_map(|line| line.strip_prefix("MemAvailable:"))
+      .and_then(|rest| {
+        rest
+          .trim()
+          .trim_end_matches("kB")
+          .trim()
+          .parse::<u64>()
+          .ok()
+      })
+      .map(|kib| kib * 1024)
+  }
+}
+
+#[tauri::command]
+fn get_available_memory() -> Option<u64> {
+  available_memory_bytes()
+}
+
+/// The running Windows version as "major.minor.build". `RtlGetVersion` is
+/// used because `GetVersionEx` reports Windows 8 to apps without a
+/// compatibility manifest.
+fn os_version() -> Option<String> {
+  #[cfg(target_os = "windows")]
+  {
+    #[repr(C)]
+    struct OsVersionInfo {
+      size: u32,
+      major: u32,
+      minor: u32,
+      build: u32,
+      platform_id: u32,
+      service_pack: [u16; 128],
+    }
+    #[link(name = "ntdll")]
+    extern "system" {
+      fn RtlGetVersion(info: *mut OsVersionInfo) -> i32;
+    }
+    // SAFETY: all-zero is a valid OsVersionInfo; `size` is set as the API requires.
+    let mut info: OsVersionInfo = unsafe { std::mem::zeroed() };
+    info.size = std::mem::size_of::<OsVersionInfo>() as u32;
+    // SAFETY: `info` is a properly sized, writable RTL_OSVERSIONINFOW.
+    if unsafe { RtlGetVersion(&mut info) } != 0 {
+      return None;
+    }
+    Some(format!("{}.{}.{}", info.major, info.minor, info.build))
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    None
+  }
+}
+
+#[tauri::command]
+fn get_os_version() -> Option<String> {
+  os_version()
+}
+
+/// Names a Windows version the way users know it: 10.0 builds from 22000 on
+/// are Windows 11.
+fn windows_version_label(version: &str) -> String {
+  match parse_version(version).as_slice() {
+    [10, 0, build, ..] if *build >= 22000 => format!("Windows 11 build {}", build),
+    [10, 0, build, ..] => format!("Windows 10 build {}", build),
+    _ => format!("Windows {}", version),
+  }
+}
+
+/// Refuses to install an app that needs a newer Windows than this one, unless
+/// `ignore_os_check` is set. Passes when the OS version cannot be read.
+fn ensure_os_supported(min_os_version: Option<&str>, ignore_os_check: bool) -> Result<(), String> {
+  let Some(required) = min_os_version
+    .map(str::trim)
+    .filter(|value| !value.is_empty())
+  else {
+    return Ok(());
+  };
+  if ignore_os_check {
+    return Ok(());
+  }
+  let Some(actual) = os_version() else {
+    return Ok(());
+  };
+  if compare_versions(&actual, required) == Ordering::Less {
+    return Err(format!(
+      "This app requires {} or newer; this PC has {}.",
+      windows_version_label(required),
+      windows_version_label(&actual)
+    ));
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn check_os_version(min_os_version: String) -> Result<(), String> {
+  ensure_os_supported(Some(&min_os_version), false)
+}
+
+fn insufficient_memory_message(available: u64) -> String {
+  format!(
+    "Insufficient memory to extract this installer: {} MB free, {} MB needed. Close other programs and try again.",
+    available / (1024 * 1024),
+    MIN_EXTRACTION_MEMORY / (1024 * 1024)
+  )
+}
+
+/// Space a shrinking `SpaceReservation` keeps released ahead of the
+/// extractor, so its next writes never compete with the reservation itself.
+const RESERVATION_HEADROOM: u64 = 64 * 1024 * 1024;
+
+/// A placeholder file next to the install folder that holds disk space for
+/// an extraction, so other programs cannot fill the disk partway through.
+/// NTFS allocates the clusters for `set_len`; file systems that create a
+/// sparse file instead only get the up-front space check. The placeholder is
+/// deleted when the reservation is dropped, including when the install fails.
+struct SpaceReservation {
+  path: PathBuf,
+  file: Option<File>,
+  bytes: u64,
+}
+
+impl SpaceReservation {
+  fn new(install_path: &Path, bytes: u64) -> Result<Self, String> {
+    let (Some(parent), Some(name)) = (install_path.parent(), install_path.file_name()) else {
+      return Err("Cannot reserve space next to a drive root.".to_string());
+    };
+    let path = parent.join(format!(".{}.reserve", name.to_string_lossy()));
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let allocated = file.set_len(bytes);
+    // Built before checking so that a failed allocation is cleaned up too.
+    let mut reservation = Self {
+      path,
+      file: Some(file),
+      bytes,
+    };
+    if let Err(error) = allocated {
+      return Err(format!(
+        "Not enough disk space to install: {} MB needed ({}).",
+        bytes.div_ceil(1024 * 1024),
+        error
+      ));
+    }
+    reservation.release_written(0);
+    Ok(reservation)
+  }
+
+  /// Gives back as much as the extractor has written so far, plus headroom.
+  fn release_written(&mut self, written: u64) {
+    let remaining = self
+      .bytes
+      .saturating_sub(written.saturating_add(RESERVATION_HEADROOM));
+    if let Some(file) = self.file.as_ref() {
+      let _ = file.set_len(remaining);
+    }
+  }
+}
+
+impl Drop for SpaceReservation {
+  fn drop(&mut self) {
+    drop(self.file.take());
+    let _ = std::fs::remove_file(&self.path);
+  }
+}
+
+/// Checks that the MSI's payload fits where it would be installed by briefly
+/// reserving the space. Returns the number of bytes needed.
+#[tauri::command]
+fn check_install_space(installer_path: String, install_dir: String) -> Result<u64, String> {
+  let needed = msi_payload_size(Path::new(&installer_path)).map_err(|e| e.to_string())?;
+  if needed > 0 {
+    SpaceReservation::new(Path::new(&install_dir), needed)?;
+  }
+  Ok(needed)
+}
+
+/// Why extracting an MSI failed, returned inside `InstallError`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ExtractionError {
+  /// "open" when the MSI could not be read, "extract" when writing out its
+  /// files failed.
+  stage: &'static str,
+  /// "io", "format" (the MSI's contents are malformed), "memory" or "crash"
+  /// (the extractor aborted).
+  kind: &'static str,
+  message: String,
+  /// The `std::io::ErrorKind` behind an I/O failure.
+  io_error_kind: Option<String>,
+  /// The first payload file, relative to the install folder, that was not
+  /// written.
+  entry: Option<String>,
+  /// Byte offset in the installer, when the extractor reported one.
+  offset: Option<u64>,
+}
+
+impl std::fmt::Display for ExtractionError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match &self.entry {
+      Some(entry) => write!(f, "{} (while extracting {})", self.message, entry),
+      None => f.write_str(&self.message),
+    }
+  }
+}
+
+impl ExtractionError {
+  /// Classifies an extractor error by the I/O error somewhere in its chain.
+  fn from_error(stage: &'static str, error: &(dyn std::error::Error + 'static)) -> Self {
+    let mut io_error = None;
+    let mut current = Some(error);
+    while let Some(error) = current {
+      if let Some(io) = error.downcast_ref::<std::io::Error>() {
+        io_error = Some(io.kind());
+        break;
+      }
+      current = error.source();
+    }
+    let malformed = matches!(
+      io_error,
+      None | Some(std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof)
+    );
+    Self::new(
+      stage,
+      if malformed { "format" } else { "io" },
+      error.to_string(),
+    )
+    .with_io_error_kind(io_error)
+  }
+
+  /// The extractor panics on some failures; its message is all there is.
+  fn from_panic(payload: Box<dyn std::any::Any + Send>) -> Self {
+    let message = payload
+      .downcast_ref::<&str>()
+      .map(|message| message.to_string())
+      .or_else(|| payload.downcast_ref::<String>().cloned())
+      .unwrap_or_else(|| "MSI extraction stopped unexpectedly.".to_string());
+    Self::new("extract", "crash", message)
+  }
+
+  fn new(stage: &'static str, kind: &'static str, message: String) -> Self {
+    let offset = error_offset(&message);
+    Self {
+      stage,
+      kind,
+      message,
+      io_error_kind: None,
+      entry: None,
+      offset,
+    }
+  }
+
+  fn with_io_error_kind(mut self, kind: Option<std::io::ErrorKind>) -> Self {
+    self.io_error_kind = kind.map(|kind| format!("{:?}", kind));
+    self
+  }
+}
+
+/// Pulls a byte offset such as "offset 4096" or "offset 0x1000" out of an
+/// error message.
+fn error_offset(message: &str) -> Option<u64> {
+  let rest = &message[message.find("offset")? + "offset".len()..];
+  let token = rest
+    .trim_start_matches([' ', ':', '='])
+    .split(|c: char| !c.is_ascii_alphanumeric())
+    .next()?;
+  match token.strip_prefix("0x") {
+    Some(hex) => u64::from_str_radix(hex, 16).ok(),
+    None => token.parse().ok(),
+  }
+}
+
+/// The first payload file of `installer` missing from `install_path`.
+fn first_missing_entry(installer: &Path, install_path: &Path) -> Option<String> {
+  msi_payload_paths(installer, install_path)
+    .ok()?
+    .into_iter()
+    .find(|path| !path.exists())
+    .map(|path| {
+      path
+        .strip_prefix(install_path)
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .to_string()
+    })
+}
+
+/// Why an install failed, as `install_msi_payload` returns it to the UI.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallError {
+  message: String,
+  /// Set when the MSI's files could not be extracted.
+  extraction: Option<Box<ExtractionError>>,
+}
+
+impl std::fmt::Display for InstallError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl From<String> for InstallError {
+  fn from(message: String) -> Self {
+    Self {
+      message,
+      extraction: None,
+    }
+  }
+}
+
+impl From<ExtractionError> for InstallError {
+  fn from(error: ExtractionError) -> Self {
+    Self {
+      message: error.to_string(),
+      extraction: Some(Box::new(error)),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ExtractionSummary {
+  total_bytes: u64,
+  duration_ms: u64,
+  bytes_per_second: u64,
+  /// How long each step of the install took, in the order they ran.
+  phases: Vec<PhaseTiming>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PhaseTiming {
+  phase: &'static str,
+  duration_ms: u64,
+}
+
+/// Records the time between successive `mark` calls as named phases.
+struct PhaseTimer {
+  lap_started: Instant,
+  phases: Vec<PhaseTiming>,
+}
+
+impl PhaseTimer {
+  fn new() -> Self {
+    PhaseTimer {
+      lap_started: Instant::now(),
+      phases: Vec::new(),
+    }
+  }
+
+  fn mark(&mut self, phase: &'static str) {
+    self.phases.push(PhaseTiming {
+      phase,
+      duration_ms: self.lap_started.elapsed().as_millis() as u64,
+    });
+    self.lap_started = Instant::now();
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum IoThrottle {
+  Low,
+  #[default]
+  Normal,
+}
+
+impl IoThrottle {
+  /// Parses "low" or "normal"; anything else is logged and treated as normal.
+  fn parse(value: Option<&str>) -> Self {
+    match value
+      .map(|value| value.trim().to_ascii_lowercase())
+      .as_deref()
+    {
+      None | Some("normal") => IoThrottle::Normal,
+      Some("low") => IoThrottle::Low,
+      Some(other) => {
+        write_log(
+          "warning",
+          &format!("ignoring unknown I/O throttle {:?}", other),
+        );
+        IoThrottle::Normal
+      }
+    }
+  }
+}
+
+/// Puts the calling thread into (or takes it out of) background mode, which
+/// lowers its disk and memory priority as well as its CPU priority.
+#[cfg(target_os = "windows")]
+fn set_thread_background_io(enabled: bool) -> Result<(), String> {
+  #[link(name = "kernel32")]
+  extern "system" {
+    fn GetCurrentThread() -> *mut std::ffi::c_void;
+    fn SetThreadPriority(thread: *mut std::ffi::c_void, priority: i32) -> i32;
+  }
+  const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+  const THREAD_MODE_BACKGROUND_END: i32 = 0x0002_0000;
+
+  let mode = if enabled {
+    THREAD_MODE_BACKGROUND_BEGIN
+  } else {
+    THREAD_MODE_BACKGROUND_END
+  };
+  // SAFETY: GetCurrentThread returns a pseudo handle that is always valid for
+  // the calling thread.
+  if unsafe { SetThreadPriority(GetCurrentThread(), mode) } == 0 {
+    return Err(std::io::Error::last_os_error().to_string());
+  }
+  Ok(())
+}
+
+/// Moves the calling thread to the lowest best-effort I/O priority, or back to
+/// the default derived from its CPU priority. `ionice` accepts a thread id for
+/// `-p`, and `/proc/thread-self` names the calling thread.
+#[cfg(not(target_os = "windows"))]
+fn set_thread_background_io(enabled: bool) -> Result<(), String> {
+  let link = std::fs::read_link("/proc/thread-self").map_err(|e| e.to_string())?;
+  let tid = link
+    .file_name()
+    .and_then(|name| name.to_str())
+    .ok_or_else(|| "Could not determine the current thread id.".to_string())?
+    .to_string();
+  let class: &[&str] = if enabled {
+    &["-c", "2", "-n", "7"]
+  } else {
+    &["-c", "0"]
+  };
+  let status = std::process::Command::new("ionice")
+    .args(class)
+    .args(["-p", &tid])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("ionice exited with code {:?}.", status.code()))
+  }
+}
+
+/// Extracts on a worker thread while this thread samples how much has landed
+/// in `install_path`, reporting `(fraction, bytes_per_second)`. The extractor
+/// itself gives no progress, so re-installs over existing files undercount
+/// until the files they replace change size. With `reserve_space` the
+/// payload's size is reserved first and released as the files land.
+fn extract_msi_with_progress<F: FnMut(f64, u64)>(
+  installer: &Path,
+  install_path: &Path,
+  io_throttle: IoThrottle,
+  reserve_space: bool,
+  mut on_progress: F,
+) -> Result<ExtractionSummary, ExtractionError> {
+  if let Some(available) = available_memory_bytes().filter(|&free| free < MIN_EXTRACTION_MEMORY) {
+    return Err(ExtractionError::new(
+      "open",
+      "memory",
+      insufficient_memory_message(available),
+    ));
+  }
+  let expected = msi_payload_size(installer).unwrap_or(0);
+  let mut reservation = if reserve_space && expected > 0 {
+    let reservation = SpaceReservation::new(install_path, expected)
+      .map_err(|message| ExtractionError::new("open", "io", message))?;
+    Some(reservation)
+  } else {
+    None
+  };
+  let baseline = directory_size(install_path);
+  let started = Instant::now();
+  let (source, target) = (installer.to_path_buf(), install_path.to_path_buf());
+  let worker = std::thread::spawn(move || -> Result<(), ExtractionError> {
+    let throttled = io_throttle == IoThrottle::Low;
+    if throttled {
+      if let Err(error) = set_thread_background_io(true) {
+        write_log(
+          "warning",
+          &format!("could not lower extraction I/O priority: {}", error),
+        );
+      }
+    }
+    let result = MsiExtractor::from_path(&source)
+      .map_err(|error| ExtractionError::from_error("open", &error))
+      .and_then(|mut extractor| {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| extractor.to(&target)))
+          .map_err(ExtractionError::from_panic)
+      });
+    if throttled {
+      let _ = set_thread_background_io(false);
+    }
+    result
+  });
+
+  // The lowest free memory seen while extracting; used to explain a failure.
+  let mut lowest_memory: Option<u64> = None;
+  while !worker.is_finished() {
+    std::thread::sleep(EXTRACTION_POLL_INTERVAL);
+    if let Some(available) = available_memory_bytes() {
+      if available < MIN_EXTRACTION_MEMORY && lowest_memory.is_none_or(|lowest| available < lowest)
+      {
+        write_log(
+          "warning",
+          &format!("{} MB free during extraction", available / (1024 * 1024)),
+        );
+      }
+      lowest_memory = Some(lowest_memory.map_or(available, |lowest| lowest.min(available)));
+    }
+    let written = directory_size(install_path).saturating_sub(baseline);
+    if let Some(reservation) = reservation.as_mut() {
+      reservation.release_written(written);
+    }
+    let fraction = if expected > 0 {
+      (written as f64 / expected as f64).min(1.0)
+    } else {
+      0.0
+    };
+    on_progress(fraction, bytes_per_second(written, started.elapsed()));
+  }
+  let low_memory = lowest_memory.filter(|&lowest| lowest < MIN_EXTRACTION_MEMORY);
+  worker
+    .join()
+    .map_err(ExtractionError::from_panic)
+    .and_then(|result| result)
+    .map_err(|mut error| {
+      if error.stage == "extract" {
+        error.entry = first_missing_entry(installer, install_path);
+      }
+      if let Some(lowest) = low_memory {
+        error.kind = "memory";
+        error.message = insufficient_memory_message(lowest);
+      }
+      error
+    })?;
+  drop(reservation);
+
+  let elapsed = started.elapsed();
+  let total_bytes = if expected > 0 {
+    expected
+  } else {
+    directory_size(install_path).saturating_sub(baseline)
+  };
+  Ok(ExtractionSummary {
+    total_bytes,
+    duration_ms: elapsed.as_millis() as u64,
+    bytes_per_second: bytes_per_second(total_bytes, elapsed),
+    phases: Vec::new(),
+  })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn install_msi_files<F: FnMut(f64, Option<u64>)>(
+  app_id: &str,
+  installer: &Path,
+  install_path: &Path,
+  exe_name: &str,
+  app_name: &str,
+  create_desktop_shortcut: bool,
+  create_start_menu_shortcut: bool,
+  io_throttle: IoThrottle,
+  reserve_space: bool,
+  mut on_progress: F,
+) -> Result<ExtractionSummary, InstallError> {
+  let mut timer = PhaseTimer::new();
+  check_installer_integrity(installer)?;
+  let exe_path = resolve_install_exe(install_path, exe_name)?;
+  let overlong = overlong_extraction_paths(installer, install_path);
+  if let Some(first) = overlong.first() {
+    return Err(
+      format!(
+        "{} file(s) would exceed the {}-character Windows path limit (for example {}). Choose a shorter install folder or enable long path support in Windows.",
+        overlong.len(),
+        MAX_PATH_CHARS,
+        first
+      )
+      .into(),
+    );
+  }
+  let linked = linked_extraction_paths(installer, install_path);
+  if let Some(first) = linked.first() {
+    return Err(
+      format!(
+        "{} path(s) would be written through a symlink or junction or outside the install folder (for example {}). Remove the link or choose another install folder.",
+        linked.len(),
+        first
+      )
+      .into(),
+    );
+  }
+  std::fs::create_dir_all(install_path).map_err(|e| e.to_string())?;
+  timer.mark("validation");
+  on_progress(0.1, None);
+
+  let mut summary = extract_msi_with_progress(
+    installer,
+    install_path,
+    io_throttle,
+    reserve_space,
+    |fraction, speed| {
+      on_progress(0.1 + fraction * 0.75, Some(speed));
+    },
+  )?;
+  timer.mark("extraction");
+  on_progress(0.85, None);
+
+  let mut shortcuts = Vec::new();
+  if create_desktop_shortcut {
+    if let Some(desktop) = tauri::api::path::desktop_dir() {
+      let shortcut = desktop.join(required_shortcut_file_name(app_name)?);
+      create_shortcut(&shortcut, &exe_path, install_path)?;
+      shortcuts.push(shortcut);
+    }
+  }
+  if create_start_menu_shortcut {
+    // Like a missing desktop, a missing Start menu only skips its shortcut.
+    match ensure_start_menu_dir() {
+      Ok(dir) => {
+        let shortcut = dir.join(required_shortcut_file_name(app_name)?);
+        create_shortcut(&shortcut, &exe_path, install_path)?;
+        shortcuts.push(shortcut);
+      }
+      Err(err) => write_log(
+        "warning",
+        &format!("skipped the Start menu shortcut: {}", err),
+      ),
+    }
+  }
+  timer.mark("shortcuts");
+  let version = msi_product_version(installer);
+  #[cfg(target_os = "windows")]
+  let registry_keys = match register_uninstall_entry(
+    app_id,
+    app_name,
+    install_path,
+    &exe_path,
+    version.as_deref(),
+  ) {
+    Ok(key) => vec![key],
+    Err(error) => {
+      write_log(
+        "warning",
+        &format!(
+          "could not register {} with Apps & features: {}",
+          app_id, error
+        ),
+      );
+      Vec::new()
+    }
+  };
+  #[cfg(not(target_os = "windows"))]
+  let registry_keys = Vec::new();
+  timer.mark("registry");
+  write_install_manifest(&InstallManifest {
+    app_id: app_id.to_string(),
+    version,
+    install_dir: install_path.to_string_lossy().to_string(),
+    exe_name: Some(exe_name.to_string()),
+    files: list_installed_files(install_path),
+    shortcuts: shortcuts
+      .iter()
+      .map(|path| path.to_string_lossy().to_string())
+      .collect(),
+    registry_keys,
+    installed_at: unix_timestamp(),
+    generated: false,
+    installer: Some(InstallerRecord {
+      installer_type: "msi".to_string(),
+      silent_args: Vec::new(),
+    }),
+  });
+  timer.mark("verification");
+  on_progress(1.0, None);
+
+  summary.phases = timer.phases;
+  Ok(summary)
+}
+
+fn emit_extraction_summary(window: &tauri::Window, app_id: &str, summary: &ExtractionSummary) {
+  let _ = window.emit(
+    "installer-extract-summary",
+    serde_json::json!({
+      "appId": app_id,
+      "totalBytes": summary.total_bytes,
+      "durationMs": summary.duration_ms,
+      "bytesPerSecond": summary.bytes_per_second,
+      "phases": summary.phases,
+    }),
+  );
+}
+
+/// What an install placed on disk, kept so it can be audited or undone later.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallManifest {
+  app_id: String,
+  version: Option<String>,
+  install_dir: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  exe_name: Option<String>,
+  files: Vec<String>,
+  shortcuts: Vec<String>,
+  registry_keys: Vec<String>,
+  installed_at: u64,
+  /// Set when the manifest was reconstructed from the install folder rather
+  /// than recorded at install time.
+  #[serde(default)]
+  generated: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  installer: Option<InstallerRecord>,
+}
+
+/// The installer technology and silent flags that last installed an app
+/// successfully. `{installDir}` stands in for the install folder in `silent_args`
+/// so the flags can be reused when the folder changes.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallerRecord {
+  installer_type: String,
+  silent_args: Vec<String>,
+}
+
+const INSTALL_DIR_PLACEHOLDER: &str = "{installDir}";
+
+/// Stores `record` in the app's manifest, reconstructing one from `install_dir`
+/// when the app was installed without a manifest.
+fn record_installer_type(app_id: &str, install_dir: Option<&str>, record: InstallerRecord) {
+  let manifest = match load_install_manifest(app_id) {
+    Some(manifest) => Some(manifest),
+    None => install_dir.and_then(|dir| generate_install_manifest(app_id, dir).ok()),
+  };
+  let Some(mut manifest) = manifest else {
+    write_log(
+      "warning",
+      &format!(
+        "no install manifest to record the installer type for {}",
+        app_id
+      ),
+    );
+    return;
+  };
+  manifest.installer = Some(record);
+  write_install_manifest(&manifest);
+}
+
+#[tauri::command]
+fn get_app_installer_type(app_id: String) -> Option<InstallerRecord> {
+  load_install_manifest(&app_id)?.installer
+}
+
+fn install_manifests_dir() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("manifests"))
+}
+
+fn install_manifest_path(app_id: &str) -> Option<PathBuf> {
+  Some(install_manifests_dir()?.join(format!("{}.json", cache_key(app_id))))
+}
+
+fn load_install_manifest(app_id: &str) -> Option<InstallManifest> {
+  let data = std::fs::read(install_manifest_path(app_id)?).ok()?;
+  serde_json::from_slice(&data).ok()
+}
+
+fn write_install_manifest(manifest: &InstallManifest) {
+  let Some(path) = install_manifest_path(&manifest.app_id) else {
+    return;
+  };
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(manifest) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+fn msi_product_version(installer: &Path) -> Option<String> {
+  let mut package = msi::open(installer).ok()?;
+  let rows = package.select_rows(msi::Select::table("Property")).ok()?;
+  for row in rows {
+    if row["Property"].as_str() == Some("ProductVersion") {
+      return row["Value"].as_str().map(str::to_string);
+    }
+  }
+  None
+}
+
+/// Files under `install_path`, relative to it, without following symlinks.
+fn list_installed_files(install_path: &Path) -> Vec<String> {
+  let mut files = Vec::new();
+  let mut pending = vec![install_path.to_path_buf()];
+  while let Some(dir) = pending.pop() {
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+      continue;
+    };
+    for entry in entries.flatten() {
+      let Ok(file_type) = entry.file_type() else {
+        continue;
+      };
+      let path = entry.path();
+      if file_type.is_dir() {
+        pending.push(path);
+      } else if let Ok(relative) = path.strip_prefix(install_path) {
+        files.push(relative.to_string_lossy().to_string());
+      }
+    }
+  }
+  files.sort();
+  files
+}
+
+/// Best-effort manifest for apps installed before manifests were recorded.
+fn generate_install_manifest(app_id: &str, install_dir: &str) -> Result<InstallManifest, String> {
+  let install_path = PathBuf::from(install_dir);
+  let metadata =
+    std::fs::metadata(&install_path).map_err(|_| "Install folder not found.".to_string())?;
+  let installed_at = metadata
+    .modified()
+    .ok()
+    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+    .map(|duration| duration.as_secs())
+    .unwrap_or(0);
+  Ok(InstallManifest {
+    app_id: app_id.to_string(),
+    version: None,
+    install_dir: install_dir.to_string(),
+    exe_name: None,
+    files: list_installed_files(&install_path),
+    shortcuts: Vec::new(),
+    registry_keys: Vec::new(),
+    installed_at,
+    generated: true,
+    installer: None,
+  })
+}
+
+/// Writes the recorded install manifest for an app to `destination`, falling
+/// back to scanning `install_dir` when nothing was recorded.
+#[tauri::command]
+fn export_app_manifest(
+  app_id: String,
+  destination: String,
+  install_dir: Option<String>,
+) -> Result<(), String> {
+  let manifest = match load_install_manifest(&app_id) {
+    Some(manifest) => manifest,
+    None => {
+      let install_dir =
+        install_dir.ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+      generate_install_manifest(&app_id, &install_dir)?
+    }
+  };
+  let data = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+  std::fs::write(destination, data).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShortcutCheck {
+  path: String,
+  target: Option<String>,
+  /// "ok", "repaired", "missing" (the .lnk itself is gone) or "broken".
+  status: &'static str,
+}
+
+#[cfg(target_os = "windows")]
+fn read_u16_le(data: &[u8], at: usize) -> Option<u16> {
+  let bytes = data.get(at..at + 2)?;
+  Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(target_os = "windows")]
+fn read_u32_le(data: &[u8], at: usize) -> Option<u32> {
+  let bytes = data.get(at..at + 4)?;
+  Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(target_os = "windows")]
+fn read_ansi_z(data: &[u8], at: usize) -> Option<String> {
+  let bytes = data.get(at..)?;
+  let end = bytes.iter().position(|byte| *byte == 0)?;
+  Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn read_utf16_z(data: &[u8], at: usize) -> Option<String> {
+  let units: Vec<u16> = data
+    .get(at..)?
+    .chunks_exact(2)
+    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+    .take_while(|unit| *unit != 0)
+    .collect();
+  Some(String::from_utf16_lossy(&units))
+}
+
+/// Where a shell link pointed when it was saved, as recorded in its LinkInfo
+/// block (MS-SHLLINK 2.3). Links identify the volume by serial number and
+/// label rather than by GUID.
+#[cfg(target_os = "windows")]
+struct LinkTarget {
+  local_path: String,
+  volume_serial: u32,
+  volume_label: String,
+}
+
+#[cfg(target_os = "windows")]
+fn read_link_target(shortcut: &Path) -> Option<LinkTarget> {
+  const HEADER_SIZE: u32 = 0x4C;
+  const HAS_LINK_TARGET_ID_LIST: u32 = 0x1;
+  const HAS_LINK_INFO: u32 = 0x2;
+  const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+  const UNICODE_LABEL_MARKER: usize = 0x14;
+
+  let data = std::fs::read(shortcut).ok()?;
+  if read_u32_le(&data, 0)? != HEADER_SIZE {
+    return None;
+  }
+  let flags = read_u32_le(&data, 20)?;
+  if flags & HAS_LINK_INFO == 0 {
+    return None;
+  }
+  let mut offset = HEADER_SIZE as usize;
+  if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+    offset += 2 + usize::from(read_u16_le(&data, offset)?);
+  }
+  let info = data.get(offset..)?;
+  if read_u32_le(info, 8)? & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+    return None;
+  }
+  let volume = info.get(read_u32_le(info, 12)? as usize..)?;
+  let local_path = read_ansi_z(info, read_u32_le(info, 16)? as usize)?;
+  let label_offset = read_u32_le(volume, 12)? as usize;
+  let volume_label = if label_offset == UNICODE_LABEL_MARKER {
+    read_utf16_z(volume, read_u32_le(volume, 16)? as usize)?
+  } else {
+    read_ansi_z(volume, label_offset)?
+  };
+  Some(LinkTarget {
+    local_path,
+    volume_serial: read_u32_le(volume, 8)?,
+    volume_label,
+  })
+}
+
+/// Mounted drives as (letter such as "E:", volume serial, volume label).
+#[cfg(target_os = "windows")]
+fn mounted_volumes() -> Vec<(String, u32, String)> {
+  let script = "Get-CimInstance Win32_LogicalDisk | ForEach-Object { \"$($_.DeviceID)|$($_.VolumeSerialNumber)|$($_.VolumeName)\" }";
+  let Ok(output) = std::process::Command::new("powershell")
+    .args([
+      "-NoProfile",
+      "-ExecutionPolicy",
+      "Bypass",
+      "-Command",
+      script,
+    ])
+    .output()
+  else {
+    return Vec::new();
+  };
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .filter_map(|line| {
+      let mut parts = line.trim().splitn(3, '|');
+      let letter = parts.next()?.to_string();
+      let serial = u32::from_str_radix(parts.next()?, 16).ok()?;
+      let label = parts.next().unwrap_or_default().to_string();
+      Some((letter, serial, label))
+    })
+    .collect()
+}
+
+/// Finds the drive that now holds the volume a link was saved against,
+/// matching on serial first and on a unique, non-empty label otherwise.
+#[cfg(target_os = "windows")]
+fn remount_letter(link: &LinkTarget, volumes: &[(String, u32, String)]) -> Option<String> {
+  if let Some((letter, _, _)) = volumes
+    .iter()
+    .find(|(_, serial, _)| *serial == link.volume_serial)
+  {
+    return Some(letter.clone());
+  }
+  if link.volume_label.is_empty() {
+    return None;
+  }
+  let mut by_label = volumes
+    .iter()
+    .filter(|(_, _, label)| label.eq_ignore_ascii_case(&link.volume_label));
+  match (by_label.next(), by_label.next()) {
+    (Some((letter, _, _)), None) => Some(letter.clone()),
+    _ => None,
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn replace_drive_letter(path: &str, letter: &str) -> Option<String> {
+  let rest = path.get(2..)?;
+  (path.as_bytes().get(1) == Some(&b':')).then(|| format!("{}{}", letter, rest))
+}
+
+/// Checks every shortcut recorded in an app's install manifest. When a
+/// target's drive letter has disappeared because a removable drive was
+/// remounted under another letter, the shortcut is rewritten to point there.
+#[tauri::command]
+fn verify_shortcuts(app_id: String) -> Result<Vec<ShortcutCheck>, String> {
+  #[cfg(target_os = "windows")]
+  {
+    let mut manifest = load_install_manifest(&app_id)
+      .ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+    let mut volumes = None;
+    let mut checks = Vec::new();
+    let mut new_letter = None;
+    for shortcut in &manifest.shortcuts {
+      let shortcut_path = PathBuf::from(shortcut);
+      if !shortcut_path.exists() {
+        checks.push(ShortcutCheck {
+          path: shortcut.clone(),
+          target: None,
+          status: "missing",
+        });
+        continue;
+      }
+      let Some(link) = read_link_target(&shortcut_path) else {
+        checks.push(ShortcutCheck {
+          path: shortcut.clone(),
+          target: None,
+          status: "broken",
+        });
+        continue;
+      };
+      let target = PathBuf::from(&link.local_path);
+      if target.exists() {
+        checks.push(ShortcutCheck {
+          path: shortcut.clone(),
+          target: Some(link.local_path),
+          status: "ok",
+        });
+        continue;
+      }
+      let drive_gone = link
+        .local_path
+        .get(..2)
+        .is_some_and(|drive| !Path::new(&format!("{}\\", drive)).exists());
+      let repaired = drive_gone
+        .then(|| {
+          let volumes = volumes.get_or_insert_with(mounted_volumes);
+          let letter = remount_letter(&link, volumes)?;
+          let moved = PathBuf::from(replace_drive_letter(&link.local_path, &letter)?);
+          if !moved.exists() {
+            return None;
+          }
+          let working_dir = moved.parent()?.to_path_buf();
+          create_shortcut(&shortcut_path, &moved, &working_dir).ok()?;
+          new_letter = Some(letter);
+          Some(moved.to_string_lossy().to_string())
+        })
+        .flatten();
+      checks.push(match repaired {
+        Some(target) => ShortcutCheck {
+          path: shortcut.clone(),
+          target: Some(target),
+          status: "repaired",
+        },
+        None => ShortcutCheck {
+          path: shortcut.clone(),
+          target: Some(link.local_path),
+          status: "broken",
+        },
+      });
+    }
+    if let Some(letter) = new_letter {
+      if let Some(install_dir) = replace_drive_letter(&manifest.install_dir, &letter) {
+        manifest.install_dir = install_dir;
+        write_install_manifest(&manifest);
+      }
+    }
+    Ok(checks)
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    let _ = app_id;
+    Err("Shortcuts are only supported on Windows.".to_string())
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelinkSummary {
+  install_dir: String,
+  /// Shortcuts rewritten to point into the new folder.
+  shortcuts: usize,
+  /// Registry values (Apps & features entry, install markers) updated.
+  registry_values: usize,
+}
+
+/// Points an app's records at the folder it was moved to by hand: the install
+/// manifest, its shortcuts and its registry entries. The app's executable has
+/// to be in `new_dir` already.
+#[tauri::command]
+fn relink_install(app_id: String, new_dir: String) -> Result<RelinkSummary, String> {
+  let mut manifest = load_install_manifest(&app_id)
+    .ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+  let exe_name = manifest
+    .exe_name
+    .clone()
+    .ok_or_else(|| "The app's executable was not recorded.".to_string())?;
+  let new_path = PathBuf::from(&new_dir);
+  let exe_path = resolve_install_exe(&new_path, &exe_name)?;
+  if !exe_path.is_file() {
+    return Err(format!("{} was not found in {}.", exe_name, new_dir));
+  }
+  let old_path = PathBuf::from(&manifest.install_dir);
+  manifest.install_dir = new_dir.clone();
+  write_install_manifest(&manifest);
+
+  #[cfg(target_os = "windows")]
+  let (shortcuts, registry_values) = {
+    let (mut shortcuts, mut registry_values) = (0, 0);
+    for shortcut in &manifest.shortcuts {
+      let shortcut_path = Path::new(shortcut);
+      if !shortcut_path.exists() {
+        continue;
+      }
+      // Keep whatever the shortcut pointed at inside the old folder.
+      let target = read_link_target(shortcut_path)
+        .and_then(|link| {
+          let relative = Path::new(&link.local_path)
+            .strip_prefix(&old_path)
+            .ok()?
+            .to_path_buf();
+          Some(new_path.join(relative))
+        })
+        .filter(|target| target.exists())
+        .unwrap_or_else(|| exe_path.clone());
+      let working_dir = target.parent().unwrap_or(&new_path).to_path_buf();
+      create_shortcut(shortcut_path, &target, &working_dir)?;
+      shortcuts += 1;
+    }
+    let install_location = new_path.to_string_lossy().to_string();
+    for key in &manifest.registry_keys {
+      if key.starts_with(UNINSTALL_KEY_ROOT) {
+        reg_add(key, Some("InstallLocation"), &install_location)?;
+        reg_add(key, Some("DisplayIcon"), &exe_path.to_string_lossy())?;
+        reg_add(key, Some("UninstallString"), &uninstall_command(&app_id)?)?;
+        registry_values += 3;
+      } else if registry_value(key, "InstallDir").is_some() {
+        reg_add(key, Some("InstallDir"), &install_location)?;
+        registry_values += 1;
+      }
+    }
+    (shortcuts, registry_values)
+  };
+  #[cfg(not(target_os = "windows"))]
+  let (shortcuts, registry_values) = {
+    let _ = old_path;
+    (0, 0)
+  };
+  Ok(RelinkSummary {
+    install_dir: new_dir,
+    shortcuts,
+    registry_values,
+  })
+}
+
+fn load_all_install_manifests() -> Vec<InstallManifest> {
+  let Some(Ok(entries)) = install_manifests_dir().map(std::fs::read_dir) else {
+    return Vec::new();
+  };
+  entries
+    .flatten()
+    .filter_map(|entry| std::fs::read(entry.path()).ok())
+    .filter_map(|data| serde_json::from_slice(&data).ok())
+    .collect()
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppStats {
+  app_id: String,
+  install_bytes: u64,
+  launches: u64,
+  last_launched_at: Option<u64>,
+  total_runtime_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HubStats {
+  installed_apps: usize,
+  total_install_bytes: u64,
+  total_downloaded_bytes: u64,
+  completed_downloads: u64,
+  total_launches: u64,
+  total_runtime_ms: u64,
+  apps: Vec<AppStats>,
+}
+
+/// Totals across the install manifests, network usage and launch stats for a
+/// stats panel. Apps that were launched but have no manifest are still listed.
+#[tauri::command]
+fn get_hub_stats() -> HubStats {
+  let mut launch_stats = load_launch_stats();
+  let mut apps: Vec<AppStats> = load_all_install_manifests()
+    .into_iter()
+    .map(|manifest| {
+      let stats = launch_stats.remove(&manifest.app_id).unwrap_or_default();
+      AppStats {
+        install_bytes: directory_size(Path::new(&manifest.install_dir)),
+        app_id: manifest.app_id,
+        launches: stats.launches,
+        last_launched_at: stats.last_launched_at,
+        total_runtime_ms: stats.total_runtime_ms,
+      }
+    })
+    .collect();
+  let installed_apps = apps.len();
+  apps.extend(launch_stats.into_iter().map(|(app_id, stats)| AppStats {
+    app_id,
+    install_bytes: 0,
+    launches: stats.launches,
+    last_launched_at: stats.last_launched_at,
+    total_runtime_ms: stats.total_runtime_ms,
+  }));
+  apps.sort_by(|a, b| a.app_id.cmp(&b.app_id));
+  let usage = load_network_usage();
+  HubStats {
+    installed_apps,
+    total_install_bytes: apps.iter().map(|app| app.install_bytes).sum(),
+    total_downloaded_bytes: usage.total_bytes,
+    completed_downloads: usage.completed_downloads,
+    total_launches: apps.iter().map(|app| app.launches).sum(),
+    total_runtime_ms: apps.iter().map(|app| app.total_runtime_ms).sum(),
+    apps,
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StartMenuReport {
+  folder: String,
+  folder_recreated: bool,
+  shortcuts: Vec<ShortcutCheck>,
+}
+
+/// Makes sure the Enderfall Start Menu folder exists and that every Start
+/// Menu shortcut recorded in an install manifest is present, recreating
+/// missing ones from the manifest's install folder and executable.
+#[tauri::command]
+fn verify_start_menu() -> Result<StartMenuReport, String> {
+  let folder = start_menu_dir().ok_or_else(|| "APPDATA is not set.".to_string())?;
+  let folder_recreated = !folder.is_dir();
+  ensure_start_menu_dir()?;
+
+  let mut shortcuts = Vec::new();
+  for manifest in load_all_install_manifests() {
+    for shortcut in &manifest.shortcuts {
+      let shortcut_path = PathBuf::from(shortcut);
+      if !shortcut_path.starts_with(&folder) {
+        continue;
+      }
+      if shortcut_path.exists() {
+        shortcuts.push(ShortcutCheck {
+          path: shortcut.clone(),
+          target: None,
+          status: "ok",
+        });
+        continue;
+      }
+      let target = manifest
+        .exe_name
+        .as_deref()
+        .and_then(|exe_name| resolve_install_exe(Path::new(&manifest.install_dir), exe_name).ok())
+        .filter(|target| target.exists());
+      let repaired = target.as_deref().is_some_and(|target| {
+        #[cfg(target_os = "windows")]
+        {
+          create_shortcut(&shortcut_path, target, Path::new(&manifest.install_dir)).is_ok()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+          let _ = target;
+          false
+        }
+      });
+      shortcuts.push(ShortcutCheck {
+        path: shortcut.clone(),
+        target: target.map(|target| target.to_string_lossy().to_string()),
+        status: if repaired { "repaired" } else { "broken" },
+      });
+    }
+  }
+
+  Ok(StartMenuReport {
+    folder: folder.to_string_lossy().to_string(),
+    folder_recreated,
+    shortcuts,
+  })
+}
+
+/// Paths Windows Defender has detected threats in, lowercased, from its threat
+/// history. Empty when Defender is unavailable.
+fn defender_threat_paths() -> Vec<String> {
+  #[cfg(target_os = "windows")]
+  {
+    let script = "Get-MpThreatDetection | ForEach-Object { $_.Resources }";
+    let Ok(output) = std::process::Command::new("powershell")
+      .args([
+        "-NoProfile",
+        "-ExecutionPolicy",
+        "Bypass",
+        "-Command",
+        script,
+      ])
+      .output()
+    else {
+      return Vec::new();
+    };
+    // Resources look like "file:_C:\Games\App\app.exe".
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .filter_map(|line| line.trim().strip_prefix("file:_"))
+      .map(|path| path.to_lowercase())
+      .collect()
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    Vec::new()
+  }
+}
+
+fn was_quarantined(path: &Path, threats: &[String]) -> bool {
+  let path = path.to_string_lossy().to_lowercase();
+  threats.contains(&path)
+}
+
+fn quarantine_message(path: &Path) -> String {
+  format!(
+    "Your antivirus removed {}. Restore it from Windows Security > Protection history, or add an exclusion for the install folder and reinstall.",
+    path.display()
+  )
+}
+
+/// Explains why an installed app's file is missing: when the install recorded
+/// it and Defender's threat history lists it, the antivirus took it.
+fn missing_app_file_error(app_id: &str, path: &Path) -> String {
+  let recorded = load_install_manifest(app_id).is_some_and(|manifest| {
+    path
+      .strip_prefix(&manifest.install_dir)
+      .is_ok_and(|relative| {
+        manifest
+          .files
+          .iter()
+          .any(|file| Path::new(file) == relative)
+      })
+  });
+  if recorded && was_quarantined(path, &defender_threat_paths()) {
+    quarantine_message(path)
+  } else {
+    "File not found.".to_string()
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallVerification {
+  /// Files recorded at install time that are no longer on disk.
+  missing: Vec<String>,
+  /// The subset of `missing` that Windows Defender quarantined.
+  quarantined: Vec<String>,
+  message: Option<String>,
+}
+
+/// Checks the files recorded in an app's install manifest against the disk and
+/// flags the ones an antivirus removed.
+#[tauri::command]
+fn verify_install(app_id: String) -> Result<InstallVerification, String> {
+  let manifest = load_install_manifest(&app_id)
+    .ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+  let install_path = Path::new(&manifest.install_dir);
+  let missing: Vec<PathBuf> = manifest
+    .files
+    .iter()
+    .map(|file| install_path.join(file))
+    .filter(|path| !path.exists())
+    .collect();
+  let threats = if missing.is_empty() {
+    Vec::new()
+  } else {
+    defender_threat_paths()
+  };
+  let quarantined: Vec<&PathBuf> = missing
+    .iter()
+    .filter(|path| was_quarantined(path, &threats))
+    .collect();
+  Ok(InstallVerification {
+    message: quarantined.first().map(|path| quarantine_message(path)),
+    quarantined: quarantined
+      .iter()
+      .map(|path| path.to_string_lossy().to_string())
+      .collect(),
+    missing: missing
+      .iter()
+      .map(|path| path.to_string_lossy().to_string())
+      .collect(),
+  })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallFingerprint {
+  /// SHA-256 over every recorded file's relative path and content hash.
+  fingerprint: String,
+  version: Option<String>,
+  files: usize,
+  /// Recorded files that are gone; they still count toward the fingerprint.
+  missing: Vec<String>,
+}
+
+/// Hashes an install's recorded files into one digest that two users can
+/// compare. Paths are sorted and use `/` so the result does not depend on the
+/// manifest's order or the platform.
+#[tauri::command]
+fn get_install_fingerprint(app_id: String) -> Result<InstallFingerprint, String> {
+  let manifest = load_install_manifest(&app_id)
+    .ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+  let install_path = Path::new(&manifest.install_dir);
+  let mut entries: Vec<(String, Option<String>)> = manifest
+    .files
+    .iter()
+    .map(|file| {
+      (
+        file.replace('\\', "/"),
+        file_sha256(&install_path.join(file)),
+      )
+    })
+    .collect();
+  entries.sort();
+  entries.dedup();
+  let mut hasher = Sha256::new();
+  for (path, hash) in &entries {
+    hasher.update(path.as_bytes());
+    hasher.update([0]);
+    hasher.update(hash.as_deref().unwrap_or("-").as_bytes());
+    hasher.update(b"\n");
+  }
+  Ok(InstallFingerprint {
+    fingerprint: format!("{:x}", hasher.finalize()),
+    version: manifest.version,
+    files: entries.len(),
+    missing: entries
+      .into_iter()
+      .filter(|(_, hash)| hash.is_none())
+      .map(|(path, _)| path)
+      .collect(),
+  })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallDirCheck {
+  empty: bool,
+  /// Another app whose recorded install lives in this folder.
+  owner: Option<String>,
+  /// Set when installing here would mix files with something else; the
+  /// install is refused unless `overwrite` is passed.
+  conflict: bool,
+}
+
+/// Looks at what is already in `install_path` before `app_id` is installed
+/// there. Reinstalling or updating the same app is never a conflict: the
+/// folder matches the app's manifest or, for installs from before manifests
+/// were recorded, already holds `exe_name`.
+fn inspect_install_dir(app_id: &str, install_path: &Path, exe_name: &str) -> InstallDirCheck {
+  let empty = std::fs::read_dir(install_path).map_or(true, |mut entries| entries.next().is_none());
+  if empty {
+    return InstallDirCheck {
+      empty,
+      owner: None,
+      conflict: false,
+    };
+  }
+  let target = canonicalize_lenient(install_path);
+  let mut owner = None;
+  for manifest in load_all_install_manifests() {
+    if canonicalize_lenient(Path::new(&manifest.install_dir)) != target {
+      continue;
+    }
+    if manifest.app_id == app_id {
+      return InstallDirCheck {
+        empty,
+        owner: None,
+        conflict: false,
+      };
+    }
+    owner = Some(manifest.app_id);
+  }
+  let same_app = owner.is_none()
+    && load_install_manifest(app_id).is_none()
+    && resolve_install_exe(install_path, exe_name).is_ok_and(|exe| exe.is_file());
+  InstallDirCheck {
+    empty,
+    conflict: !same_app,
+    owner,
+  }
+}
+
+/// Refuses to extract into a folder that already holds other files unless
+/// `overwrite` is set.
+fn ensure_install_dir_available(
+  app_id: &str,
+  install_path: &Path,
+  exe_name: &str,
+  overwrite: bool,
+) -> Result<(), String> {
+  let check = inspect_install_dir(app_id, install_path, exe_name);
+  if !check.conflict || overwrite {
+    return Ok(());
+  }
+  Err(match check.owner {
+    Some(owner) => format!(
+      "Install folder is not empty: {} is already installed in {}. Choose another folder or overwrite.",
+      owner,
+      install_path.display()
+    ),
+    None => format!(
+      "Install folder is not empty: {} already contains other files. Choose another folder or overwrite.",
+      install_path.display()
+    ),
+  })
+}
+
+#[tauri::command]
+fn check_install_dir(app_id: String, install_dir: String, exe_name: String) -> InstallDirCheck {
+  inspect_install_dir(&app_id, Path::new(&install_dir), &exe_name)
+}
+
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn install_msi_payload(
+  window: tauri::Window,
+  app_id: String,
+  installer_path: String,
+  install_dir: String,
+  exe_name: String,
+  app_name: String,
+  create_desktop_shortcut: bool,
+  create_start_menu_shortcut: bool,
+  operation_id: Option<String>,
+  io_throttle: Option<String>,
+  overwrite: Option<bool>,
+  override_pin: Option<bool>,
+  install_marker: Option<InstallMarker>,
+  reserve_space: Option<bool>,
+  min_os_version: Option<String>,
+  ignore_os_check: Option<bool>,
+) -> Result<Vec<PhaseTiming>, InstallError> {
+  let installer = PathBuf::from(&installer_path);
+  if !installer.exists() {
+    return Err("Installer not found.".to_string().into());
+  }
+  ensure_os_supported(min_os_version.as_deref(), ignore_os_check.unwrap_or(false))?;
+  let app = window.app_handle();
+  ensure_msi_installer(&app, &app_id, &installer)?;
+  ensure_pin_allows(&app_id, &installer, override_pin.unwrap_or(false))?;
+  ensure_bitness_fits(
+    &app,
+    Some(&app_id),
+    &installer,
+    Some(Path::new(&install_dir)),
+  )?;
+  ensure_network_location_available(Path::new(&install_dir))?;
+  ensure_install_dir_available(
+    &app_id,
+    Path::new(&install_dir),
+    &exe_name,
+    overwrite.unwrap_or(false),
+  )?;
+
+  Deferral::new(&app, &app_id).wait()?;
+  let state = app.state::<AppState>();
+  let _slot = state.install_slots.acquire(&app, &app_id);
+  ensure_installer_unchanged(&installer)?;
+  let mut progress = ProgressEmitter::new(&window, &app_id, operation_id.as_deref());
+  let summary = install_msi_files(
+    &app_id,
+    &installer,
+    Path::new(&install_dir),
+    &exe_name,
+    &app_name,
+    create_desktop_shortcut,
+    create_start_menu_shortcut,
+    IoThrottle::parse(io_throttle.as_deref()),
+    reserve_space.unwrap_or(false),
+    |value, speed| {
+      progress.set_bytes_per_second(speed);
+      progress.update(None, value);
+    },
+  )
+  .inspect_err(|error| {
+    report_failure(&state, "install", &app_id, Some("msi"), &error.message);
+    record_failed_install(FailedInstall {
+      app_id: app_id.clone(),
+      installer_path: installer_path.clone(),
+      install_dir: install_dir.clone(),
+      exe_name: exe_name.clone(),
+      app_name: app_name.clone(),
+      create_desktop_shortcut,
+      create_start_menu_shortcut,
+      error: error.message.clone(),
+      failed_at: unix_timestamp(),
+    })
+  })?;
+  clear_failed_install(&app_id);
+  if let Some(marker) = install_marker.as_ref() {
+    write_install_marker(&app_id, Path::new(&install_dir), marker)?;
+  }
+  progress.finish(None);
+  emit_extraction_summary(&window, &app_id, &summary);
+  Ok(summary.phases)
+}
+
+/// Tells an installed app where it lives so it can find itself and hand its
+/// updates to the hub.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallMarker {
+  /// "json" writes an `InstallLocation` file at `path`, relative to the install
+  /// folder; "registry" writes its fields as values under `HKCU\Software\<path>`.
+  format: String,
+  path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallLocation {
+  app_id: String,
+  install_dir: String,
+  version: Option<String>,
+  /// The hub executable, for apps that want to ask it to update them.
+  hub_path: Option<String>,
+  installed_at: u64,
+}
+
+/// Writes `marker` for a finished install. JSON markers live inside the install
+/// folder and go with it; registry markers are added to the install manifest
+/// so uninstalling removes them.
+fn write_install_marker(
+  app_id: &str,
+  install_path: &Path,
+  marker: &InstallMarker,
+) -> Result<(), String> {
+  let manifest = load_install_manifest(app_id);
+  let location = InstallLocation {
+    app_id: app_id.to_string(),
+    install_dir: install_path.to_string_lossy().to_string(),
+    version: manifest
+      .as_ref()
+      .and_then(|manifest| manifest.version.clone()),
+    hub_path: get_current_exe_path().ok(),
+    installed_at: unix_timestamp(),
+  };
+  match marker.format.as_str() {
+    "json" => {
+      let name = Path::new(&marker.path);
+      if marker.path.trim().is_empty() || name.has_root() || name.is_absolute() {
+        return Err(format!(
+          "{} is not a path inside the install folder.",
+          marker.path
+        ));
+      }
+      let target = ensure_inside_install_dir(install_path, &install_path.join(name))?;
+      if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+      }
+      let data = serde_json::to_vec_pretty(&location).map_err(|e| e.to_string())?;
+      std::fs::write(&target, data).map_err(|e| e.to_string())
+    }
+    "registry" => {
+      let subkey = marker.path.trim().trim_matches('\\');
+      if subkey.is_empty()
+        || subkey
+          .split('\\')
+          .any(|part| part.is_empty() || part == "..")
+      {
+        return Err(format!("{} is not a valid registry key.", marker.path));
+      }
+      #[cfg(target_os = "windows")]
+      {
+        let key = format!(r"HKCU\Software\{}", subkey);
+        reg_add(&key, Some("AppId"), &location.app_id)?;
+        reg_add(&key, Some("InstallDir"), &location.install_dir)?;
+        if let Some(version) = location.version.as_deref() {
+          reg_add(&key, Some("Version"), version)?;
+        }
+        if let Some(hub_path) = location.hub_path.as_deref() {
+          reg_add(&key, Some("HubPath"), hub_path)?;
+        }
+        if let Some(mut manifest) = manifest {
+          if !manifest.registry_keys.contains(&key) {
+            manifest.registry_keys.push(key);
+            write_install_manifest(&manifest);
+          }
+        }
+        Ok(())
+      }
+      #[cfg(not(target_os = "windows"))]
+      {
+        Err("Registry markers are only supported on Windows.".to_string())
+      }
+    }
+    other => Err(format!("Unknown install marker format \"{}\".", other)),
+  }
+}
+
+/// What is needed to re-run an MSI install that failed, kept until the app
+/// installs successfully.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FailedInstall {
+  app_id: String,
+  installer_path: String,
+  install_dir: String,
+  exe_name: String,
+  app_name: String,
+  create_desktop_shortcut: bool,
+  create_start_menu_shortcut: bool,
+  error: String,
+  failed_at: u64,
+}
+
+/// Re-runs an install elevated or after a restart; the hub handles it without
+/// opening a window and exits with a non-zero code on failure.
+const RETRY_INSTALL_FLAG: &str = "--retry-install";
+
+fn failed_installs_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("failed-installs.json"))
+}
+
+fn load_failed_installs() -> HashMap<String, FailedInstall> {
+  failed_installs_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn write_failed_installs(failed: &HashMap<String, FailedInstall>) {
+  let Some(path) = failed_installs_path() else {
+    return;
+  };
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(failed) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+fn record_failed_install(install: FailedInstall) {
+  let mut failed = load_failed_installs();
+  failed.insert(install.app_id.clone(), install);
+  write_failed_installs(&failed);
+}
+
+fn clear_failed_install(app_id: &str) {
+  let mut failed = load_failed_installs();
+  if failed.remove(app_id).is_some() {
+    write_failed_installs(&failed);
+  }
+}
+
+/// Reports kept while the endpoint is unreachable; the oldest are dropped
+/// beyond this.
+const TELEMETRY_QUEUE_CAPACITY: usize = 200;
+const TELEMETRY_TIMEOUT: Duration = Duration::from_secs(10);
+const TELEMETRY_MESSAGE_MAX_CHARS: usize = 300;
+
+/// Serializes access to the telemetry queue file between the commands and
+/// the background flushes.
+static TELEMETRY_QUEUE_LOCK: Mutex<()> = Mutex::new(());
+
+/// An anonymous report of a failed install or download, only ever built when
+/// the user has turned on `telemetry_enabled`. It carries no machine id, user
+/// name, file path or URL: the error message is scrubbed of all three, and
+/// the rest is the app's catalogue id and the platform the hub runs on.
+/// Reports are queued in `telemetry-queue.json`, which `get_telemetry_queue`
+/// shows as-is, and posted as `{"reports": [...]}` to `telemetry_endpoint`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FailureReport {
+  /// "install" or "download".
+  kind: String,
+  app_id: String,
+  /// OS error or process exit code found in the error, if any.
+  error_code: Option<i64>,
+  message: String,
+  installer_type: Option<String>,
+  os: String,
+  arch: String,
+  hub_version: String,
+  occurred_at: u64,
+}
+
+fn telemetry_queue_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("telemetry-queue.json"))
+}
+
+fn load_telemetry_queue() -> Vec<FailureReport> {
+  telemetry_queue_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn write_telemetry_queue(queue: &[FailureReport]) {
+  let Some(path) = telemetry_queue_path() else {
+    return;
+  };
+  if queue.is_empty() {
+    let _ = std::fs::remove_file(path);
+    return;
+  }
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(queue) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+fn clear_telemetry_queue() {
+  if let Ok(_guard) = TELEMETRY_QUEUE_LOCK.lock() {
+    write_telemetry_queue(&[]);
+  }
+}
+
+/// Replaces every word that could point back at a machine or person (paths,
+/// URLs, addresses, host and file names, the user's name) and caps the
+/// length.
+fn scrub_error_message(message: &str) -> String {
+  let user = std::env::var("USERNAME")
+    .or_else(|_| std::env::var("USER"))
+    .ok()
+    .map(|user| user.to_lowercase())
+    .filter(|user| user.len() > 1);
+  scrub_message_words(message, user.as_deref())
+}
+
+/// `scrub_error_message` with the (lowercase) user name passed in. Quotes,
+/// brackets and sentence punctuation around a word are kept.
+fn scrub_message_words(message: &str, user: Option<&str>) -> String {
+  let words: Vec<String> = message
+    .split_whitespace()
+    .map(|word| {
+      let core = word.trim_start_matches(['"', '\'', '(', '<', '`']);
+      let prefix = &word[..word.len() - core.len()];
+      let core = core.trim_end_matches(['"', '\'', ')', '>', '`', ',', ';', ':', '.', '!', '?']);
+      let suffix = &word[prefix.len() + core.len()..];
+      match sensitive_word_marker(core, user) {
+        Some(marker) => format!("{}{}{}", prefix, marker, suffix),
+        None => word.to_string(),
+      }
+    })
+    .collect();
+  words
+    .join(" ")
+    .chars()
+    .take(TELEMETRY_MESSAGE_MAX_CHARS)
+    .collect()
+}
+
+/// What a word of an error message is replaced with, if anything.
+fn sensitive_word_marker(word: &str, user: Option<&str>) -> Option<&'static str> {
+  if word.is_empty() {
+    return None;
+  }
+  let lower = word.to_lowercase();
+  // Slashes, backslashes and their escapes cover paths, URLs and UNC shares.
+  if word.contains(['\\', '/']) || lower.contains("%5c") || lower.contains("%2f") {
+    return Some("<path>");
+  }
+  if user.is_some_and(|user| lower.contains(user)) {
+    return Some("<user>");
+  }
+  // Mail addresses and WebDAV hosts such as `server@SSL@443`.
+  if word.contains('@') {
+    return Some("<address>");
+  }
+  let unbracketed = word.trim_matches(['[', ']']);
+  if word.parse::<std::net::SocketAddr>().is_ok() || unbracketed.parse::<std::net::IpAddr>().is_ok()
+  {
+    return Some("<address>");
+  }
+  let is_label = |label: &str| {
+    !label.is_empty()
+      && label
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+  };
+  if let Some((host, port)) = word.rsplit_once(':') {
+    if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) && host.split('.').all(is_label)
+    {
+      return Some("<address>");
+    }
+  }
+  // Host and file names: dotted labels ending in something with a letter,
+  // so version numbers like 1.2.3 stay.
+  if let Some((_, last)) = word.rsplit_once('.') {
+    if word.split('.').all(is_label) && last.chars().any(|c| c.is_ascii_alphabetic()) {
+      return Some("<name>");
+    }
+  }
+  None
+}
+
+/// Pulls the code out of "(os error 5)" or "exited with code Some(1603)".
+fn failure_error_code(message: &str) -> Option<i64> {
+  ["os error ", "code Some("].iter().find_map(|marker| {
+    let rest = &message[message.find(marker)? + marker.len()..];
+    let end = rest
+      .char_indices()
+      .find(|(index, c)| !(c.is_ascii_digit() || (*index == 0 && *c == '-')))
+      .map_or(rest.len(), |(index, _)| index);
+    rest[..end].parse().ok()
+  })
+}
+
+/// Posts every queued report in one request and clears the queue once the
+/// endpoint accepts them. Returns how many were sent.
+fn flush_telemetry_queue(client: &Client, endpoint: &str) -> Result<usize, String> {
+  let _guard = TELEMETRY_QUEUE_LOCK.lock().map_err(|e| e.to_string())?;
+  let queue = load_telemetry_queue();
+  if queue.is_empty() {
+    return Ok(0);
+  }
+  let body =
+    serde_json::to_vec(&serde_json::json!({ "reports": queue })).map_err(|e| e.to_string())?;
+  client
+    .post(endpoint)
+    .header(reqwest::header::CONTENT_TYPE, "application/json")
+    .timeout(TELEMETRY_TIMEOUT)
+    .body(body)
+    .send()
+    .and_then(|response| response.error_for_status())
+    .map_err(|e| request_error_message(&e))?;
+  write_telemetry_queue(&[]);
+  Ok(queue.len())
+}
+
+/// Queues a failure report when the user opted in and tries to send the
+/// queue in the background. Never fails and never delays the caller: a
+/// report that cannot be sent stays queued for the next flush.
+fn report_failure(
+  state: &AppState,
+  kind: &str,
+  app_id: &str,
+  installer_type: Option<&str>,
+  error: &str,
+) {
+  let prefs = current_preferences(state);
+  if !prefs.telemetry_enabled {
+    return;
+  }
+  let report = FailureReport {
+    kind: kind.to_string(),
+    app_id: app_id.to_string(),
+    error_code: failure_error_code(error),
+    message: scrub_error_message(error),
+    installer_type: installer_type.map(str::to_string),
+    os: std::env::consts::OS.to_string(),
+    arch: std::env::consts::ARCH.to_string(),
+    hub_version: env!("CARGO_PKG_VERSION").to_string(),
+    occurred_at: unix_timestamp(),
+  };
+  if let Ok(_guard) = TELEMETRY_QUEUE_LOCK.lock() {
+    let mut queue = load_telemetry_queue();
+    queue.push(report);
+    let excess = queue.len().saturating_sub(TELEMETRY_QUEUE_CAPACITY);
+    queue.drain(..excess);
+    write_telemetry_queue(&queue);
+  }
+  let (Some(endpoint), Ok(client)) = (prefs.telemetry_endpoint, shared_http_client(state)) else {
+    return;
+  };
+  std::thread::spawn(move || {
+    let _ = flush_telemetry_queue(&client, &endpoint);
+  });
+}
+
+/// The failure reports waiting to be sent, exactly as they will be posted.
+#[tauri::command]
+fn get_telemetry_queue() -> Vec<FailureReport> {
+  let _guard = TELEMETRY_QUEUE_LOCK.lock();
+  load_telemetry_queue()
+}
+
+/// Sends the queued failure reports now. Returns how many were sent.
+#[tauri::command]
+fn flush_telemetry(state: tauri::State<AppState>) -> Result<usize, String> {
+  let prefs = current_preferences(&state);
+  if !prefs.telemetry_enabled {
+    return Err("Failure reporting is turned off.".to_string());
+  }
+  let endpoint = prefs
+    .telemetry_endpoint
+    .ok_or_else(|| "No telemetry endpoint is set.".to_string())?;
+  flush_telemetry_queue(&shared_http_client(&state)?, &endpoint)
+}
+
+/// Runs a recorded failed install again in this process.
+fn rerun_failed_install<F: FnMut(f64, Option<u64>)>(
+  install: &FailedInstall,
+  on_progress: F,
+) -> Result<ExtractionSummary, String> {
+  let installer = Path::new(&install.installer_path);
+  if !installer.exists() {
+    return Err("The cached installer is gone; download the app again.".to_string());
+  }
+  ensure_installer_unchanged(installer)?;
+  let result = install_msi_files(
+    &install.app_id,
+    installer,
+    Path::new(&install.install_dir),
+    &install.exe_name,
+    &install.app_name,
+    install.create_desktop_shortcut,
+    install.create_start_menu_shortcut,
+    IoThrottle::Normal,
+    false,
+    on_progress,
+  )
+  .map_err(|error| error.message);
+  match &result {
+    Ok(_) => clear_failed_install(&install.app_id),
+    Err(error) => record_failed_install(FailedInstall {
+      error: error.clone(),
+      failed_at: unix_timestamp(),
+      ..install.clone()
+    }),
+  }
+  result
+}
+
+/// Handles `--retry-install <app_id>` for elevated and post-restart retries.
+fn retry_install_from_args(app_id: &str) -> Result<(), String> {
+  let install = load_failed_installs()
+    .remove(app_id)
+    .ok_or_else(|| format!("No failed install recorded for {}.", app_id))?;
+  rerun_failed_install(&install, |_, _| {}).map(|_| ())
+}
+
+#[cfg(target_os = "windows")]
+fn retry_install_elevated(app_id: &str) -> Result<(), String> {
+  let exe = get_current_exe_path()?;
+  let script = format!(
+    "$p = Start-Process -FilePath '{}' -ArgumentList '{}','\"{}\"' -Verb RunAs -Wait -PassThru; exit $p.ExitCode",
+    exe.replace('\'', "''"),
+    RETRY_INSTALL_FLAG,
+    app_id.replace('\'', "''")
+  );
+  let status = std::process::Command::new("powershell")
+    .args([
+      "-NoProfile",
+      "-ExecutionPolicy",
+      "Bypass",
+      "-Command",
+      &script,
+    ])
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    return Ok(());
+  }
+  Err(
+    load_failed_installs()
+      .remove(app_id)
+      .map(|install| install.error)
+      .unwrap_or_else(|| "The elevated install was cancelled or failed.".to_string()),
+  )
+}
+
+#[cfg(target_os = "windows")]
+const RUN_ONCE_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\RunOnce";
+
+#[cfg(target_os = "windows")]
+fn retry_run_once_name(app_id: &str) -> String {
+  format!("EnderfallRetry.{}", cache_key(app_id))
+}
+
+#[cfg(target_os = "windows")]
+fn schedule_retry_after_reboot(app_id: &str) -> Result<(), String> {
+  reg_add(
+    RUN_ONCE_KEY,
+    Some(&retry_run_once_name(app_id)),
+    &format!(
+      "\"{}\" {} \"{}\"",
+      get_current_exe_path()?,
+      RETRY_INSTALL_FLAG,
+      app_id
+    ),
+  )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RetryOutcome {
+  app_id: String,
+  strategy: String,
+  /// "installed" or "scheduled".
+  status: &'static str,
+}
+
+/// Retries the last failed MSI install for `app_id` from the cached installer.
+/// `strategy` escalates from "normal" (retry in place) to "elevated" (rerun
+/// the hub as administrator for the install) and "after_reboot" (run it once
+/// at the next sign-in, after locked files have been released).
+#[tauri::command(async)]
+fn retry_install(
+  window: tauri::Window,
+  app_id: String,
+  strategy: String,
+) -> Result<RetryOutcome, String> {
+  let install = load_failed_installs()
+    .remove(&app_id)
+    .ok_or_else(|| "No failed install to retry for this app.".to_string())?;
+  let status = match strategy.as_str() {
+    "normal" => {
+      let app = window.app_handle();
+      let state = app.state::<AppState>();
+      let _slot = state.install_slots.acquire(&app, &app_id);
+      let mut progress = ProgressEmitter::new(&window, &app_id, None);
+      let summary = rerun_failed_install(&install, |value, speed| {
+        progress.set_bytes_per_second(speed);
+        progress.update(None, value);
+      })?;
+      progress.finish(None);
+      emit_extraction_summary(&window, &app_id, &summary);
+      "installed"
+    }
+    #[cfg(target_os = "windows")]
+    "elevated" => {
+      retry_install_elevated(&app_id)?;
+      "installed"
+    }
+    #[cfg(target_os = "windows")]
+    "after_reboot" => {
+      schedule_retry_after_reboot(&app_id)?;
+      "scheduled"
+    }
+    #[cfg(not(target_os = "windows"))]
+    "elevated" | "after_reboot" => {
+      return Err(format!(
+        "The {} strategy is only supported on Windows.",
+        strategy
+      ));
+    }
+    other => return Err(format!("Unknown retry strategy {:?}.", other)),
+  };
+  Ok(RetryOutcome {
+    app_id,
+    strategy,
+    status,
+  })
+}
+
+#[tauri::command]
+fn get_failed_install(app_id: String) -> Option<FailedInstall> {
+  load_failed_installs().remove(&app_id)
+}
+
+fn installer_file_name(url: &str) -> &str {
+  url
+    .split('/')
+    .next_back()
+    .filter(|name| !name.is_empty())
+    .unwrap_or("installer.bin")
+}
+
+fn partial_download_path(destination: &Path) -> PathBuf {
+  let mut name = destination.file_name().unwrap_or_default().to_os_string();
+  name.push(".part");
+  destination.with_file_name(name)
+}
+
+fn partial_meta_path(destination: &Path) -> PathBuf {
+  let mut name = destination.file_name().unwrap_or_default().to_os_string();
+  name.push(".part.meta");
+  destination.with_file_name(name)
+}
+
+/// Sidecar describing what a `.part` file is a prefix of, so a resume never
+/// appends fresh bytes onto a partial from a different URL or version.
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PartialDownloadMeta {
+  url: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  total_size: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  sha256: Option<String>,
+}
+
+fn read_partial_meta(destination: &Path) -> Option<PartialDownloadMeta> {
+  let data = std::fs::read(partial_meta_path(destination)).ok()?;
+  serde_json::from_slice(&data).ok()
+}
+
+fn discard_partial_download(destination: &Path) {
+  let _ = std::fs::remove_file(partial_download_path(destination));
+  let _ = std::fs::remove_file(partial_meta_path(destination));
+}
+
+/// Reads the full size of the resource from a `Content-Range: bytes a-b/total`
+/// header.
+fn content_range_total(response: &reqwest::blocking::Response) -> Option<u64> {
+  let value = response
+    .headers()
+    .get(reqwest::header::CONTENT_RANGE)?
+    .to_str()
+    .ok()?;
+  value.rsplit('/').next()?.trim().parse().ok()
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+struct NetworkUsage {
+  /// Bytes received over the network across every download, resumed or not.
+  total_bytes: u64,
+  completed_downloads: u64,
+}
+
+/// Serializes updates of the network usage file across concurrent downloads.
+static NETWORK_USAGE_LOCK: Mutex<()> = Mutex::new(());
+
+fn network_usage_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("network-usage.json"))
+}
+
+fn load_network_usage() -> NetworkUsage {
+  network_usage_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn record_network_usage(bytes: u64, completed: bool) {
+  if bytes == 0 && !completed {
+    return;
+  }
+  let _guard = NETWORK_USAGE_LOCK.lock();
+  let Some(path) = network_usage_path() else {
+    return;
+  };
+  let mut usage = load_network_usage();
+  usage.total_bytes = usage.total_bytes.saturating_add(bytes);
+  if completed {
+    usage.completed_downloads += 1;
+  }
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(&usage) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+/// Base64 SHA-256 of a DER certificate's public key (its
+/// SubjectPublicKeyInfo), in the same form as an HPKP `pin-sha256`.
+fn certificate_spki_sha256(certificate: &[u8]) -> Result<String, String> {
+  let certificate = CertificateDer::from(certificate);
+  let parsed = rustls::server::ParsedCertificate::try_from(&certificate)
+    .map_err(|e| format!("Could not read the server certificate: {}", e))?;
+  let spki = parsed.subject_public_key_info();
+  Ok(base64::engine::general_purpose::STANDARD.encode(Sha256::digest(spki.as_ref())))
+}
+
+/// Returns the public-key pin of the server behind `url`, for filling in
+/// `expected_spki_sha256`.
+#[tauri::command]
+fn get_certificate_pin(url: String, state: tauri::State<AppState>) -> Result<String, String> {
+  check_download_allowed(&current_preferences(&state), &url)?;
+  let response = shared_http_client(&state)?
+    .head(&url)
+    .send()
+    .map_err(|e| request_error_message(&e))?;
+  let certificate = response
+    .extensions()
+    .get::<reqwest::tls::TlsInfo>()
+    .and_then(|info| info.peer_certificate())
+    .ok_or_else(|| "The server did not present a TLS certificate.".to_string())?;
+  certificate_spki_sha256(certificate)
+}
+
+/// Where `download_to_sink` puts the bytes it receives. Sinks that keep
+/// data between runs can offer it back so the download resumes with a Range
+/// request instead of starting over.
+trait DownloadSink {
+  /// Length of the bytes held from an interrupted download of `url` that
+  /// are safe to continue from. Anything unusable is thrown away first.
+  fn resumable_len(&mut self, _url: &str, _expected_sha256: Option<&str>) -> u64 {
+    0
+  }
+
+  /// Size of the whole resource when the held bytes were first requested,
+  /// used to notice a remote file that changed since.
+  fn recorded_total(&self) -> Option<u64> {
+    None
+  }
+
+  /// Drops any held bytes so the download can start over.
+  fn discard(&mut self) {}
+
+  /// Called once the response is accepted. A resumed sink feeds the bytes it
+  /// already holds into `hasher` so the final hash covers the whole file.
+  fn begin(
+    &mut self,
+    url: &str,
+    total_size: Option<u64>,
+    expected_sha256: Option<&str>,
+    resumed: bool,
+    hasher: &mut Sha256,
+  ) -> Result<(), String>;
+
+  fn write(&mut self, chunk: &[u8]) -> Result<(), String>;
+
+  /// Called after the last chunk; the download only succeeds if this does.
+  fn finish(&mut self) -> Result<(), String>;
+}
+
+/// Writes into a `.part` file beside `destination` and renames it into place
+/// when complete. A leftover partial is resumed when its `.part.meta` sidecar
+/// still matches the URL, expected size, and expected hash.
+struct FileSink {
+  destination: PathBuf,
+  meta: Option<PartialDownloadMeta>,
+  output: Option<File>,
+}
+
+impl FileSink {
+  fn new(destination: &Path) -> Self {
+    FileSink {
+      destination: destination.to_path_buf(),
+      meta: None,
+      output: None,
+    }
+  }
+}
+
+impl DownloadSink for FileSink {
+  fn resumable_len(&mut self, url: &str, expected_sha256: Option<&str>) -> u64 {
+    let partial = partial_download_path(&self.destination);
+    self.meta = read_partial_meta(&self.destination);
+    let existing = std::fs::metadata(&partial)
+      .map(|meta| meta.len())
+      .unwrap_or(0);
+    if existing == 0 {
+      return 0;
+    }
+    let matches = self.meta.as_ref().is_some_and(|meta| {
+      meta.url == url
+        && match (meta.sha256.as_deref(), expected_sha256) {
+          (Some(recorded), Some(expected)) => recorded.eq_ignore_ascii_case(expected),
+          _ => true,
+        }
+    });
+    if matches {
+      existing
+    } else {
+      self.discard();
+      0
+    }
+  }
+
+  fn recorded_total(&self) -> Option<u64> {
+    self.meta.as_ref().and_then(|meta| meta.total_size)
+  }
+
+  fn discard(&mut self) {
+    self.output = None;
+    self.meta = None;
+    discard_partial_download(&self.destination);
+  }
+
+  fn begin(
+    &mut self,
+    url: &str,
+    total_size: Option<u64>,
+    expected_sha256: Option<&str>,
+    resumed: bool,
+    hasher: &mut Sha256,
+  ) -> Result<(), String> {
+    let partial = partial_download_path(&self.destination);
+    let output = if resumed {
+      let mut previous = File::open(&partial).map_err(|e| e.to_string())?;
+      std::io::copy(&mut previous, hasher).map_err(|e| e.to_string())?;
+      std::fs::OpenOptions::new()
+        .append(true)
+        .open(&partial)
+        .map_err(|e| e.to_string())?
+    } else {
+      let meta = PartialDownloadMeta {
+        url: url.to_string(),
+        total_size,
+        sha256: expected_sha256.map(str::to_string),
+      };
+      let data = serde_json::to_vec_pretty(&meta).map_err(|e| e.to_string())?;
+      std::fs::write(partial_meta_path(&self.destination), data).map_err(|e| e.to_string())?;
+      File::create(&partial).map_err(|e| e.to_string())?
+    };
+    self.output = Some(output);
+    Ok(())
+  }
+
+  fn write(&mut self, chunk: &[u8]) -> Result<(), String> {
+    let output = self
+      .output
+      .as_mut()
+      .ok_or_else(|| "Download sink was not started.".to_string())?;
+    output.write_all(chunk).map_err(|e| e.to_string())
+  }
+
+  fn finish(&mut self) -> Result<(), String> {
+    if let Some(mut output) = self.output.take() {
+      output.flush().map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(partial_download_path(&self.destination), &self.destination)
+      .map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(partial_meta_path(&self.destination));
+    Ok(())
+  }
+}
+
+/// Streams `url` into `sink`, calling `on_progress(copied, total)` after every
+/// chunk (`total` is 0 when the server sends no length). Bytes the sink holds
+/// from an interrupted run are resumed with a Range request, and the download
+/// starts over when the server cannot continue them or the file changed.
+/// Returns the lowercase hex SHA-256 of the complete content.
+fn download_to_sink<S: DownloadSink, F: FnMut(u64, u64)>(
+  client: &Client,
+  url: &str,
+  sink: &mut S,
+  expected_sha256: Option<&str>,
+  mut on_progress: F,
+) -> Result<String, String> {
+  let existing = sink.resumable_len(url, expected_sha256);
+  let mut request = client.get(url);
+  if existing > 0 {
+    request = request.header(reqwest::header::RANGE, format!("bytes={}-", existing));
+  }
+  let mut response = request.send().map_err(|e| request_error_message(&e))?;
+  if existing > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+    sink.discard();
+    return download_to_sink(client, url, sink, expected_sha256, on_progress);
+  }
+  if !response.status().is_success() {
+    return Err(format!(
+      "Failed to download installer: {}",
+      response.status()
+    ));
+  }
+
+  let resumed = existing > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+  if resumed {
+    let recorded_size = sink.recorded_total();
+    let remote_size = content_range_total(&response);
+    if recorded_size.is_some() && remote_size.is_some() && recorded_size != remote_size {
+      drop(response);
+      sink.discard();
+      return download_to_sink(client, url, sink, expected_sha256, on_progress);
+    }
+  }
+
+  let mut hasher = Sha256::new();
+  sink.begin(
+    url,
+    response.content_length(),
+    expected_sha256,
+    resumed,
+    &mut hasher,
+  )?;
+  let mut copied: u64 = if resumed { existing } else { 0 };
+  let total = response
+    .content_length()
+    .map(|length| length.saturating_add(copied))
+    .unwrap_or(0);
+  let mut buffer = [0u8; 1024 * 256];
+  let received_from = copied;
+
+  loop {
+    let read = match response.read(&mut buffer) {
+      Ok(read) => read,
+      Err(error) => {
+        record_network_usage(copied - received_from, false);
+        return Err(error.to_string());
+      }
+    };
+    if read == 0 {
+      break;
+    }
+    sink.write(&buffer[..read])?;
+    hasher.update(&buffer[..read]);
+    copied += read as u64;
+    on_progress(copied, total);
+  }
+  record_network_usage(copied - received_from, true);
+
+  sink.finish()?;
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Downloads `url` into `destination` through a `FileSink`, resuming a
+/// leftover `.part` file when it still matches.
+fn download_to_file<F: FnMut(u64, u64)>(
+  client: &Client,
+  url: &str,
+  destination: &Path,
+  expected_sha256: Option<&str>,
+  on_progress: F,
+) -> Result<String, String> {
+  let mut sink = FileSink::new(destination);
+  download_to_sink(client, url, &mut sink, expected_sha256, on_progress)
+}
+
+/// Marks a destination as being downloaded for as long as the guard lives.
+struct ActiveDownload<'a> {
+  state: &'a AppState,
+  path: PathBuf,
+}
+
+impl<'a> ActiveDownload<'a> {
+  fn begin(state: &'a AppState, path: &Path) -> Result<Self, String> {
+    let mut active = state.active_downloads.lock().map_err(|e| e.to_string())?;
+    if !active.insert(path.to_path_buf()) {
+      return Err("This installer is already being downloaded.".to_string());
+    }
+    Ok(ActiveDownload {
+      state,
+      path: path.to_path_buf(),
+    })
+  }
+}
+
+impl Drop for ActiveDownload<'_> {
+  fn drop(&mut self) {
+    if let Ok(mut active) = self.state.active_downloads.lock() {
+      active.remove(&self.path);
+    }
+  }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallerIndexEntry {
+  app_id: String,
+  url: String,
+  downloaded_at: u64,
+  /// Hash of the file as downloaded, checked again right before it is run.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  sha256: Option<String>,
+}
+
+fn installer_index_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("installer-index.json"))
+}
+
+/// Maps downloaded installer paths to the app they were fetched for.
+fn load_installer_index() -> HashMap<String, InstallerIndexEntry> {
+  installer_index_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn write_installer_index(index: &HashMap<String, InstallerIndexEntry>) {
+  let Some(path) = installer_index_path() else {
+    return;
+  };
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(index) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+fn record_cached_installer(path: &Path, app_id: &str, url: &str, sha256: Option<&str>) {
+  let mut index = load_installer_index();
+  index.insert(
+    path.to_string_lossy().to_string(),
+    InstallerIndexEntry {
+      app_id: app_id.to_string(),
+      url: url.to_string(),
+      downloaded_at: unix_timestamp(),
+      sha256: sha256.map(str::to_string),
+    },
+  );
+  write_installer_index(&index);
+}
+
+/// Re-hashes a downloaded installer just before it runs and compares it with
+/// the hash recorded at download time, so a file swapped or modified in
+/// between is never executed. On a mismatch the file is deleted and has to be
+/// downloaded again. Files the hub did not download have nothing to compare
+/// against and pass.
+fn ensure_installer_unchanged(path: &Path) -> Result<(), String> {
+  match load_installer_index()
+    .remove(path.to_string_lossy().as_ref())
+    .and_then(|entry| entry.sha256)
+  {
+    Some(expected) => ensure_installer_hash(path, &expected),
+    None => Ok(()),
+  }
+}
+
+fn ensure_installer_hash(path: &Path, expected: &str) -> Result<(), String> {
+  if file_sha256(path).is_some_and(|actual| actual.eq_ignore_ascii_case(expected)) {
+    return Ok(());
+  }
+  let _ = std::fs::remove_file(path);
+  forget_cached_installer(path);
+  Err("The installer changed on disk after it was downloaded. Download it again.".to_string())
+}
+
+#[tauri::command]
+fn verify_cached_installer(path: String) -> Result<(), String> {
+  ensure_installer_unchanged(Path::new(&path))
+}
+
+fn forget_cached_installer(path: &Path) {
+  let mut index = load_installer_index();
+  if index.remove(path.to_string_lossy().as_ref()).is_some() {
+    write_installer_index(&index);
+  }
+}
+
+/// Checks a finished download against the size and type it was expected to
+/// have, for servers that publish no checksum. The type must match both the
+/// file name's extension and, for installer formats `detect_installer_kind`
+/// knows, the file's contents.
+fn check_download_shape(
+  path: &Path,
+  expected_size: Option<u64>,
+  expected_extension: Option<&str>,
+) -> Result<(), String> {
+  if let Some(expected) = expected_size {
+    let actual = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if actual != expected {
+      return Err(format!(
+        "Downloaded file is {} bytes but {} were expected. Please try again.",
+        actual, expected
+      ));
+    }
+  }
+  let Some(expected) = expected_extension else {
+    return Ok(());
+  };
+  let expected = expected.trim().trim_start_matches('.').to_ascii_lowercase();
+  let extension = path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .map(|ext| ext.to_ascii_lowercase());
+  if extension.as_deref() != Some(expected.as_str()) {
+    return Err(format!("Downloaded file is not a .{} file.", expected));
+  }
+  let detected = detect_installer_kind(path);
+  let contents_match = match expected.as_str() {
+    "msi" => detected == Some("msi"),
+    "exe" => matches!(detected, Some("exe" | "inno" | "nsis")),
+    "msix" | "appx" => detected == Some("msix"),
+    "zip" => matches!(detected, Some("zip" | "msix")),
+    _ => true,
+  };
+  if contents_match {
+    Ok(())
+  } else {
+    Err(format!(
+      "Downloaded file does not contain a valid .{} file.",
+      expected
+    ))
+  }
+}
+
+/// Runs the same size and type checks `download_installer` does on a file
+/// that is already on disk, without deleting it.
+#[tauri::command]
+fn check_downloaded_file(
+  path: String,
+  expected_size: Option<u64>,
+  expected_extension: Option<String>,
+) -> Result<(), String> {
+  check_download_shape(
+    Path::new(&path),
+    expected_size,
+    expected_extension.as_deref(),
+  )
+}
+
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn download_installer(
+  window: tauri::Window,
+  app_id: String,
+  url: String,
+  destination_dir: String,
+  operation_id: Option<String>,
+  expected_spki_sha256: Option<String>,
+  expected_size: Option<u64>,
+  expected_extension: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<String, String> {
+  let prefs = current_preferences(&state);
+  check_download_allowed(&prefs, &url)?;
+  // A client of its own, so the redirect chain it records is this download's.
+  let redirects = Arc::new(Mutex::new(Vec::new()));
+  let options = ClientOptions {
+    redirects: Some(redirects.clone()),
+    spki_pin: expected_spki_sha256.as_deref(),
+  };
+  let client = build_http_client_with(&prefs, options)?;
+  let dest_dir = PathBuf::from(&destination_dir);
+  ensure_network_location_available(&dest_dir)?;
+  std::fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+
+  let destination = dest_dir.join(installer_file_name(&url));
+  let _active = ActiveDownload::begin(&state, &destination)?;
+  let mut progress = ProgressEmitter::new(&window, &app_id, operation_id.as_deref());
+  let app = window.app_handle();
+  let mut deferral = Deferral::new(&app, &app_id);
+  let downloaded = download_to_file(&client, &url, &destination, None, |copied, total| {
+    deferral.throttle(copied);
+    progress.update_bytes(None, copied, total, 1.0);
+  });
+  let redirects = redirects
+    .lock()
+    .map(|chain| chain.clone())
+    .unwrap_or_default();
+  let _ = window.emit(
+    "download-resolved",
+    serde_json::json!({
+      "appId": app_id,
+      "operationId": operation_id,
+      "url": url,
+      "finalUrl": redirects.last().unwrap_or(&url),
+      "redirects": redirects,
+      "succeeded": downloaded.is_ok(),
+    }),
+  );
+  let hash =
+    downloaded.inspect_err(|error| report_failure(&state, "download", &app_id, None, error))?;
+  if let Err(err) = check_download_shape(&destination, expected_size, expected_extension.as_deref())
+  {
+    let _ = std::fs::remove_file(&destination);
+    report_failure(&state, "download", &app_id, None, &err);
+    return Err(err);
+  }
+  progress.finish(None);
+  record_cached_installer(&destination, &app_id, &url, Some(&hash));
+
+  Ok(destination.to_string_lossy().to_string())
+}
+
+/// Tries per part in `download_and_reassemble`; later attempts resume from the
+/// bytes already on disk.
+const PART_DOWNLOAD_ATTEMPTS: u32 = 3;
+const PART_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Downloads an installer split into parts, then joins them in order into
+/// `output_name` inside `destination_dir`. Each part resumes and retries on
+/// its own and is checked against `part_sha256` when given; the joined file is
+/// checked against `expected_sha256`. Progress covers the whole set. On
+/// failure the parts and any partial output are removed.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn download_and_reassemble(
+  window: tauri::Window,
+  app_id: String,
+  urls: Vec<String>,
+  output_name: String,
+  destination_dir: String,
+  part_sha256: Option<Vec<String>>,
+  expected_sha256: Option<String>,
+  operation_id: Option<String>,
+  expected_spki_sha256: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<String, String> {
+  if urls.is_empty() {
+    return Err("No parts to download.".to_string());
+  }
+  if part_sha256
+    .as_ref()
+    .is_some_and(|hashes| hashes.len() != urls.len())
+  {
+    return Err("Expected one checksum per part.".to_string());
+  }
+  let plain_name = Path::new(&output_name)
+    .file_name()
+    .and_then(|name| name.to_str());
+  if plain_name != Some(output_name.as_str()) {
+    return Err(format!("{} is not a plain file name.", output_name));
+  }
+  let prefs = current_preferences(&state);
+  for url in &urls {
+    check_download_allowed(&prefs, url)?;
+  }
+  let client = download_client(&state, expected_spki_sha256.as_deref())?;
+  let dest_dir = PathBuf::from(&destination_dir);
+  ensure_network_location_available(&dest_dir)?;
+  std::fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+  let destination = dest_dir.join(&output_name);
+  let _active = ActiveDownload::begin(&state, &destination)?;
+  let parts_dir = dest_dir.join(format!(".{}.parts", output_name));
+  std::fs::create_dir_all(&parts_dir).map_err(|e| e.to_string())?;
+
+  let mut progress = ProgressEmitter::new(&window, &app_id, operation_id.as_deref());
+  let app = window.app_handle();
+  let mut deferral = Deferral::new(&app, &app_id);
+  let part_count = urls.len() as f64;
+  let mut parts = Vec::new();
+  let mut completed_bytes = 0u64;
+  let mut result = Ok(());
+  for (index, url) in urls.iter().enumerate() {
+    let part = parts_dir.join(format!("part{}", index + 1));
+    let expected = part_sha256.as_ref().map(|hashes| hashes[index].trim());
+    let mut attempt = 0;
+    let outcome = loop {
+      attempt += 1;
+      let downloaded = download_to_file(&client, url, &part, expected, |copied, total| {
+        deferral.throttle(completed_bytes + copied);
+        match progress_fraction(copied, total) {
+          Some(fraction) => progress.update(None, (index as f64 + fraction) / part_count),
+          None => progress.update_indeterminate(None, completed_bytes + copied),
+        }
+      })
+      .and_then(|hash| match expected {
+        Some(expected) if !hash.eq_ignore_ascii_case(expected) => {
+          let _ = std::fs::remove_file(&part);
+          Err(format!("Part {} failed its checksum.", index + 1))
+        }
+        _ => Ok(()),
+      });
+      match downloaded {
+        Err(error) if attempt < PART_DOWNLOAD_ATTEMPTS => {
+          write_log(
+            "warning",
+            &format!("retrying part {} of {}: {}", index + 1, output_name, error),
+          );
+          std::thread::sleep(PART_RETRY_DELAY * attempt);
+        }
+        other => break other,
+      }
+    };
+    if let Err(error) = outcome {
+      result = Err(format!(
+        "Part {} of {} failed: {}",
+        index + 1,
+        urls.len(),
+        error
+      ));
+      break;
+    }
+    completed_bytes += std::fs::metadata(&part).map(|meta| meta.len()).unwrap_or(0);
+    parts.push(part);
+  }
+
+  let result = result.and_then(|()| {
+    let temp_path = dest_dir.join(format!("{}.tmp", output_name));
+    let joined = (|| -> std::io::Result<String> {
+      let mut output = File::create(&temp_path)?;
+      let mut hasher = Sha256::new();
+      let mut buffer = vec![0u8; 1024 * 256];
+      for part in &parts {
+        let mut input = File::open(part)?;
+        loop {
+          let read = input.read(&mut buffer)?;
+          if read == 0 {
+            break;
+          }
+          output.write_all(&buffer[..read])?;
+          hasher.update(&buffer[..read]);
+        }
+      }
+      output.flush()?;
+      Ok(format!("{:x}", hasher.finalize()))
+    })()
+    .map_err(|e| e.to_string());
+    let verified = joined.and_then(|hash| match expected_sha256.as_deref() {
+      Some(expected) if !hash.eq_ignore_ascii_case(expected.trim()) => {
+        Err("Reassembled installer checksum mismatch. Please try again.".to_string())
+      }
+      _ => std::fs::rename(&temp_path, &destination)
+        .map(|()| hash)
+        .map_err(|e| e.to_string()),
+    });
+    if verified.is_err() {
+      let _ = std::fs::remove_file(&temp_path);
+    }
+    verified
+  });
+  let _ = std::fs::remove_dir_all(&parts_dir);
+  let hash = result?;
+
+  progress.finish(None);
+  record_cached_installer(&destination, &app_id, &urls[0], Some(&hash));
+  Ok(destination.to_string_lossy().to_string())
+}
+
+/// Share of the combined progress bar given to the download phase of
+/// `download_and_install`; extraction fills the remainder.
+const DOWNLOAD_PHASE_WEIGHT: f64 = 0.6;
+
+/// Everything needed to download an MSI and extract it into place.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct InstallJob {
+  app_id: String,
+  url: String,
+  install_dir: String,
+  exe_name: String,
+  app_name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  expected_sha256: Option<String>,
+  /// Public-key pin every server the installer comes from has to match.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  expected_spki_sha256: Option<String>,
+  #[serde(default)]
+  create_desktop_shortcut: bool,
+  #[serde(default)]
+  create_start_menu_shortcut: bool,
+  /// "low" extracts with background I/O priority; defaults to normal.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  io_throttle: Option<String>,
+  /// Install even if the folder already holds another app's files.
+  #[serde(default)]
+  overwrite: bool,
+  /// Install even if the app is pinned to a different version.
+  #[serde(default)]
+  override_pin: bool,
+  /// Oldest Windows version the app runs on, as "major.minor.build".
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  min_os_version: Option<String>,
+  #[serde(default)]
+  ignore_os_check: bool,
+  /// Written into place once the files are extracted.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  install_marker: Option<InstallMarker>,
+  /// Holds the disk space the extraction needs before it starts.
+  #[serde(default)]
+  reserve_space: bool,
+}
+
+fn run_install_job(
+  window: &tauri::Window,
+  client: &Client,
+  job: &InstallJob,
+  operation_id: Option<&str>,
+  keep_installer: bool,
+) -> Result<String, String> {
+  let state = window.state::<AppState>();
+  ensure_os_supported(job.min_os_version.as_deref(), job.ignore_os_check)?;
+  check_download_allowed(&current_preferences(&state), &job.url)?;
+  ensure_network_location_available(Path::new(&job.install_dir))?;
+  ensure_install_dir_available(
+    &job.app_id,
+    Path::new(&job.install_dir),
+    &job.exe_name,
+    job.overwrite,
+  )?;
+  let cache_dir = installer_cache_dir().ok_or("Missing local data dir")?;
+  std::fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+  let installer = cache_dir.join(installer_file_name(&job.url));
+  let active = ActiveDownload::begin(&state, &installer)?;
+
+  let mut timer = PhaseTimer::new();
+  let mut progress = ProgressEmitter::new(window, &job.app_id, operation_id);
+  let expected_sha256 = job.expected_sha256.as_deref();
+  let app = window.app_handle();
+  let mut deferral = Deferral::new(&app, &job.app_id);
+  let pinned = job
+    .expected_spki_sha256
+    .as_deref()
+    .map(|pin| download_client(&state, Some(pin)))
+    .transpose()?;
+  let client = pinned.as_ref().unwrap_or(client);
+  let hash = download_to_file(
+    client,
+    &job.url,
+    &installer,
+    expected_sha256,
+    |copied, total| {
+      deferral.throttle(copied);
+      progress.update_bytes(Some("download"), copied, total, DOWNLOAD_PHASE_WEIGHT);
+    },
+  )
+  .inspect_err(|error| report_failure(&state, "download", &job.app_id, Some("msi"), error))?;
+  drop(active);
+  progress.flush();
+  timer.mark("download");
+
+  let report = |error: &String| report_failure(&state, "install", &job.app_id, Some("msi"), error);
+  if let Some(expected) = job.expected_sha256.as_deref() {
+    if !hash.eq_ignore_ascii_case(expected.trim()) {
+      let _ = std::fs::remove_file(&installer);
+      forget_cached_installer(&installer);
+      let error = "Installer checksum mismatch. Please try again.".to_string();
+      report(&error);
+      return Err(error);
+    }
+  }
+  if let Err(err) = scan_before_install(&current_preferences(&state), &installer) {
+    let _ = std::fs::remove_file(&installer);
+    forget_cached_installer(&installer);
+    report(&err);
+    return Err(err);
+  }
+  timer.mark("scan");
+  // From here on the installer is kept so `retry_install` can reuse it.
+  let record_failure = |error: &String| {
+    record_cached_installer(&installer, &job.app_id, &job.url, Some(&hash));
+    report(error);
+    record_failed_install(FailedInstall {
+      app_id: job.app_id.clone(),
+      installer_path: installer.to_string_lossy().to_string(),
+      install_dir: job.install_dir.clone(),
+      exe_name: job.exe_name.clone(),
+      app_name: job.app_name.clone(),
+      create_desktop_shortcut: job.create_desktop_shortcut,
+      create_start_menu_shortcut: job.create_start_menu_shortcut,
+      error: error.clone(),
+      failed_at: unix_timestamp(),
+    })
+  };
+  ensure_msi_installer(&app, &job.app_id, &installer).inspect_err(&record_failure)?;
+  ensure_pin_allows(&job.app_id, &installer, job.override_pin).inspect_err(&record_failure)?;
+
+  deferral.wait().inspect_err(&record_failure)?;
+  timer.mark("deferred");
+  let slot = state.install_slots.acquire(&app, &job.app_id);
+  timer.mark("waiting");
+  ensure_installer_hash(&installer, &hash).inspect_err(&record_failure)?;
+  let mut summary = install_msi_files(
+    &job.app_id,
+    &installer,
+    Path::new(&job.install_dir),
+    &job.exe_name,
+    &job.app_name,
+    job.create_desktop_shortcut,
+    job.create_start_menu_shortcut,
+    IoThrottle::parse(job.io_throttle.as_deref()),
+    job.reserve_space,
+    |fraction, speed| {
+      progress.set_bytes_per_second(speed);
+      progress.update(
+        Some("install"),
+        DOWNLOAD_PHASE_WEIGHT + fraction * (1.0 - DOWNLOAD_PHASE_WEIGHT),
+      );
+    },
+  )
+  .map_err(|error| error.message)
+  .inspect_err(&record_failure)?;
+  drop(slot);
+  clear_failed_install(&job.app_id);
+  if let Some(marker) = job.install_marker.as_ref() {
+    write_install_marker(&job.app_id, Path::new(&job.install_dir), marker)?;
+  }
+  progress.finish(Some("install"));
+  summary.phases.splice(0..0, timer.phases);
+  emit_extraction_summary(window, &job.app_id, &summary);
+
+  if keep_installer {
+    record_cached_installer(&installer, &job.app_id, &job.url, Some(&hash));
+  } else {
+    let _ = std::fs::remove_file(&installer);
+    forget_cached_installer(&installer);
+  }
+
+  Ok(hash)
+}
+
+/// Downloads an MSI into the hub's installer cache and extracts it into
+/// `install_dir` as one operation. Extraction needs the complete compound file,
+/// so the phases run back to back rather than overlapping. The cached installer
+/// is deleted after a successful install unless `keep_installer` is set.
+/// Both phases report through one `installer-progress` stream: the download
+/// fills up to `DOWNLOAD_PHASE_WEIGHT` with phase "download", extraction the
+/// rest with phase "install". Returns the SHA-256 of the downloaded installer.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)]
+fn download_and_install(
+  window: tauri::Window,
+  app_id: String,
+  url: String,
+  install_dir: String,
+  exe_name: String,
+  app_name: String,
+  expected_sha256: Option<String>,
+  expected_spki_sha256: Option<String>,
+  keep_installer: bool,
+  create_desktop_shortcut: bool,
+  create_start_menu_shortcut: bool,
+  operation_id: Option<String>,
+  io_throttle: Option<String>,
+  overwrite: Option<bool>,
+  override_pin: Option<bool>,
+  min_os_version: Option<String>,
+  ignore_os_check: Option<bool>,
+  install_marker: Option<InstallMarker>,
+  reserve_space: Option<bool>,
+  state: tauri::State<AppState>,
+) -> Result<String, String> {
+  let client = shared_http_client(&state)?;
+  let job = InstallJob {
+    app_id,
+    url,
+    install_dir,
+    exe_name,
+    app_name,
+    expected_sha256,
+    expected_spki_sha256,
+    create_desktop_shortcut,
+    create_start_menu_shortcut,
+    io_throttle,
+    overwrite: overwrite.unwrap_or(false),
+    override_pin: override_pin.unwrap_or(false),
+    min_os_version,
+    ignore_os_check: ignore_os_check.unwrap_or(false),
+    install_marker,
+    reserve_space: reserve_space.unwrap_or(false),
+  };
+  run_install_job(
+    &window,
+    &client,
+    &job,
+    operation_id.as_deref(),
+    keep_installer,
+  )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedInstaller {
+  path: String,
+  name: String,
+  size: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  app_id: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  last_accessed: Option<u64>,
+}
+
+/// Lists every installer the hub knows about: everything indexed by a
+/// download plus any stray files in the hub's own installer cache. Index
+/// entries whose file has since disappeared are pruned.
+#[tauri::command]
+fn list_cached_installers() -> Vec<CachedInstaller> {
+  let mut index = load_installer_index();
+  let before = index.len();
+  index.retain(|path, _| Path::new(path).is_file());
+  if index.len() != before {
+    write_installer_index(&index);
+  }
+
+  let mut paths: Vec<PathBuf> = index.keys().map(PathBuf::from).collect();
+  if let Some(entries) = installer_cache_dir().and_then(|dir| std::fs::read_dir(dir).ok()) {
+    for entry in entries.flatten() {
+      let path = entry.path();
+      let is_partial = path
+        .extension()
+        .is_some_and(|ext| ext == "part" || ext == "meta");
+      if path.is_file() && !is_partial && !paths.contains(&path) {
+        paths.push(path);
+      }
+    }
+  }
+
+  let mut installers: Vec<CachedInstaller> = paths
+    .into_iter()
+    .filter_map(|path| {
+      let meta = std::fs::metadata(&path).ok()?;
+      let last_accessed = meta
+        .accessed()
+        .or_else(|_| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs());
+      let key = path.to_string_lossy().to_string();
+      Some(CachedInstaller {
+        name: path.file_name()?.to_string_lossy().to_string(),
+        size: meta.len(),
+        app_id: index.get(&key).map(|entry| entry.app_id.clone()),
+        last_accessed,
+        path: key,
+      })
+    })
+    .collect();
+  installers.sort_by_key(|installer| std::cmp::Reverse(installer.last_accessed));
+  installers
+}
+
+/// Removes one cached installer. Only files the hub downloaded itself can be
+/// deleted, and never while they are still being written.
+#[tauri::command]
+fn delete_cached_installer(path: String, state: tauri::State<AppState>) -> Result<(), String> {
+  let target = PathBuf::from(&path);
+  let indexed = load_installer_index().contains_key(&path);
+  let in_cache_dir =
+    installer_cache_dir().is_some_and(|dir| target.parent() == Some(dir.as_path()));
+  if !indexed && !in_cache_dir {
+    return Err("Only cached installers can be deleted.".to_string());
+  }
+  let active = state.active_downloads.lock().map_err(|e| e.to_string())?;
+  if active.contains(&target) {
+    return Err("This installer is still downloading.".to_string());
+  }
+  drop(active);
+
+  if target.exists() {
+    std::fs::remove_file(&target).map_err(|e| e.to_string())?;
+  }
+  discard_partial_download(&target);
+  forget_cached_installer(&target);
+  Ok(())
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+enum BatchItemStatus {
+  #[default]
+  Pending,
+  Running,
+  Completed,
+  Failed,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BatchInstallItem {
+  #[serde(flatten)]
+  job: InstallJob,
+  #[serde(default)]
+  status: BatchItemStatus,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct BatchInstallState {
+  items: Vec<BatchInstallItem>,
+}
+
+impl BatchInstallState {
+  fn is_finished(&self) -> bool {
+    self
+      .items
+      .iter()
+      .all(|item| item.status == BatchItemStatus::Completed)
+  }
+}
+
+fn batch_install_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("batch-install.json"))
+}
+
+fn load_batch_install() -> Option<BatchInstallState> {
+  let data = std::fs::read(batch_install_path()?).ok()?;
+  serde_json::from_slice(&data).ok()
+}
+
+fn write_batch_install(batch: &BatchInstallState) -> Result<(), String> {
+  let path = batch_install_path().ok_or("Missing local data dir")?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let data = serde_json::to_vec_pretty(batch).map_err(|e| e.to_string())?;
+  std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn clear_batch_install() {
+  if let Some(path) = batch_install_path() {
+    let _ = std::fs::remove_file(path);
+  }
+}
+
+/// Returns the persisted batch when a previous run stopped before every item
+/// completed.
+fn interrupted_batch_install() -> Option<BatchInstallState> {
+  load_batch_install().filter(|batch| !batch.is_finished())
+}
+
+/// Works through every item that has not completed yet, persisting the batch
+/// after each status change so a closed or crashed hub can pick up where it
+/// stopped. Interrupted downloads resume from their `.part` file; failed items
+/// keep their error and are retried on the next resume.
+fn run_batch_install(
+  window: &tauri::Window,
+  state: &AppState,
+  mut batch: BatchInstallState,
+) -> Result<BatchInstallState, String> {
+  let client = shared_http_client(state)?;
+  write_batch_install(&batch)?;
+
+  for index in 0..batch.items.len() {
+    if batch.items[index].status == BatchItemStatus::Completed {
+      continue;
+    }
+    batch.items[index].status = BatchItemStatus::Running;
+    batch.items[index].error = None;
+    write_batch_install(&batch)?;
+    let _ = window.emit("batch-item-status", &batch.items[index]);
+
+    match run_install_job(window, &client, &batch.items[index].job, None, false) {
+      Ok(_) => batch.items[index].status = BatchItemStatus::Completed,
+      Err(error) => {
+        batch.items[index].status = BatchItemStatus::Failed;
+        batch.items[index].error = Some(error);
+      }
+    }
+    write_batch_install(&batch)?;
+    let _ = window.emit("batch-item-status", &batch.items[index]);
+  }
+
+  if batch.is_finished() {
+    clear_batch_install();
+  }
+  Ok(batch)
+}
+
+/// Runs off the main thread, since a batch can take as long as all of its
+/// downloads and installs together.
+#[tauri::command(async)]
+fn start_batch_install(
+  window: tauri::Window,
+  jobs: Vec<InstallJob>,
+  state: tauri::State<AppState>,
+) -> Result<BatchInstallState, String> {
+  let batch = BatchInstallState {
+    items: jobs
+      .into_iter()
+      .map(|job| BatchInstallItem {
+        job,
+        status: BatchItemStatus::Pending,
+        error: None,
+      })
+      .collect(),
+  };
+  run_batch_install(&window, &state, batch)
+}
+
+#[tauri::command(async)]
+fn resume_batch_install(
+  window: tauri::Window,
+  state: tauri::State<AppState>,
+) -> Result<BatchInstallState, String> {
+  let batch = interrupted_batch_install().ok_or("No interrupted batch install to resume.")?;
+  run_batch_install(&window, &state, batch)
+}
+
+#[tauri::command]
+fn get_interrupted_batch_install() -> Option<BatchInstallState> {
+  interrupted_batch_install()
+}
+
+#[tauri::command]
+fn discard_batch_install() {
+  clear_batch_install();
+}
+
+/// How far an item's progress must move before the queue is written again.
+const QUEUE_PERSIST_STEP: f64 = 0.05;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+enum QueuedDownloadStatus {
+  #[default]
+  Queued,
+  Downloading,
+  Completed,
+  Failed,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct QueuedDownload {
+  id: String,
+  app_id: String,
+  url: String,
+  destination_dir: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  expected_spki_sha256: Option<String>,
+  #[serde(default)]
+  progress: f64,
+  #[serde(default)]
+  status: QueuedDownloadStatus,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  path: Option<String>,
+}
+
+impl QueuedDownload {
+  fn is_finished(&self) -> bool {
+    self.status == QueuedDownloadStatus::Completed
+  }
+}
+
+fn download_queue_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("download-queue.json"))
+}
+
+fn write_download_queue(queue: &[QueuedDownload]) {
+  let Some(path) = download_queue_path() else {
+    return;
+  };
+  if let Some(parent) = path.parent() {
+    let _ = std::fs::create_dir_all(parent);
+  }
+  if let Ok(data) = serde_json::to_vec_pretty(queue) {
+    let _ = std::fs::write(path, data);
+  }
+}
+
+/// Loads the queue saved by a previous session. Items that were mid-download
+/// go back to queued; their `.part` file lets them resume with a Range request.
+fn restore_download_queue() -> Vec<QueuedDownload> {
+  let mut queue: Vec<QueuedDownload> = download_queue_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default();
+  for item in &mut queue {
+    if item.status == QueuedDownloadStatus::Downloading {
+      item.status = QueuedDownloadStatus::Queued;
+    }
+  }
+  queue
+}
+
+/// Applies `change` to the queued item with `id`, saves the queue and tells the
+/// frontend about the new state of the item.
+fn update_queued_download(
+  app: &tauri::AppHandle,
+  id: &str,
+  change: impl FnOnce(&mut QueuedDownload),
+) {
+  let state = app.state::<AppState>();
+  let Ok(mut queue) = state.download_queue.lock() else {
+    return;
+  };
+  let Some(item) = queue.iter_mut().find(|item| item.id == id) else {
+    return;
+  };
+  change(item);
+  let item = item.clone();
+  write_download_queue(&queue);
+  drop(queue);
+  let _ = app.emit_all("download-queue-item", item);
+}
+
+/// Claims the next queued item, or clears the running flag while still
+/// holding the queue lock so a concurrent `queue_download` starts a new worker.
+fn next_queued_download(state: &AppState) -> Option<QueuedDownload> {
+  let queue = state.download_queue.lock().ok()?;
+  let next = queue
+    .iter()
+    .find(|item| item.status == QueuedDownloadStatus::Queued)
+    .cloned();
+  if next.is_none() {
+    if let Ok(mut running) = state.download_worker_running.lock() {
+      *running = false;
+    }
+  }
+  next
+}
+
+fn run_queued_download(app: &tauri::AppHandle, item: &QueuedDownload) -> Result<PathBuf, String> {
+  let state = app.state::<AppState>();
+  check_download_allowed(&current_preferences(&state), &item.url)?;
+  let client = download_client(&state, item.expected_spki_sha256.as_deref())?;
+  let dest_dir = PathBuf::from(&item.destination_dir);
+  ensure_network_location_available(&dest_dir)?;
+  std::fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+  let destination = dest_dir.join(installer_file_name(&item.url));
+  let _active = ActiveDownload::begin(&state, &destination)?;
+
+  let mut persisted = item.progress;
+  let mut deferral = Deferral::new(app, &item.app_id);
+  let hash = download_to_file(&client, &item.url, &destination, None, |copied, total| {
+    deferral.throttle(copied);
+    let Some(fraction) = progress_fraction(copied, total) else {
+      return;
+    };
+    if fraction - persisted >= QUEUE_PERSIST_STEP {
+      persisted = fraction;
+      update_queued_download(app, &item.id, |entry| entry.progress = fraction);
+    }
+  })?;
+  record_cached_installer(&destination, &item.app_id, &item.url, Some(&hash));
+  Ok(destination)
+}
+
+/// Starts the background worker unless one is already draining the queue.
+fn ensure_download_worker(app: &tauri::AppHandle) {
+  let state = app.state::<AppState>();
+  if let Ok(mut running) = state.download_worker_running.lock() {
+    if *running {
+      return;
+    }
+    *running = true;
+  }
+  let app = app.clone();
+  std::thread::spawn(move || {
+    while let Some(item) = next_queued_download(&app.state::<AppState>()) {
+      update_queued_download(&app, &item.id, |entry| {
+        entry.status = QueuedDownloadStatus::Downloading;
+        entry.error = None;
+      });
+      let result = run_queued_download(&app, &item);
+      match &result {
+        Ok(_) => notify(&app, "Download complete", installer_file_name(&item.url)),
+        Err(error) => notify(&app, "Download failed", error),
+      }
+      update_queued_download(&app, &item.id, |entry| match result {
+        Ok(path) => {
+          entry.status = QueuedDownloadStatus::Completed;
+          entry.progress = 1.0;
+          entry.path = Some(path.to_string_lossy().to_string());
+        }
+        Err(error) => {
+          entry.status = QueuedDownloadStatus::Failed;
+          entry.error = Some(error);
+        }
+      });
+    }
+  });
+}
+
+/// Adds a download to the persistent queue and starts working through it.
+#[tauri::command]
+fn queue_download(
+  app: tauri::AppHandle,
+  app_id: String,
+  url: String,
+  destination_dir: String,
+  expected_spki_sha256: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<QueuedDownload, String> {
+  check_download_allowed(&current_preferences(&state), &url)?;
+  let item = QueuedDownload {
+    id: format!("{}-{}", cache_key(&app_id), unix_timestamp_millis()),
+    app_id,
+    url,
+    destination_dir,
+    expected_spki_sha256,
+    progress: 0.0,
+    status: QueuedDownloadStatus::Queued,
+    error: None,
+    path: None,
+  };
+  {
+    let mut queue = state.download_queue.lock().map_err(|e| e.to_string())?;
+    if queue.iter().any(|queued| {
+      !queued.is_finished()
+        && queued.url == item.url
+        && queued.destination_dir == item.destination_dir
+    }) {
+      return Err("That download is already queued.".to_string());
+    }
+    queue.push(item.clone());
+    write_download_queue(&queue);
+  }
+  ensure_download_worker(&app);
+  Ok(item)
+}
+
+#[tauri::command]
+fn get_download_queue(state: tauri::State<AppState>) -> Vec<QueuedDownload> {
+  state
+    .download_queue
+    .lock()
+    .map(|queue| queue.clone())
+    .unwrap_or_default()
+}
+
+/// Re-queues failed items and restarts the worker, e.g. after the
+/// `downloads-restored` event on startup.
+#[tauri::command]
+fn resume_download_queue(app: tauri::AppHandle, state: tauri::State<AppState>) {
+  if let Ok(mut queue) = state.download_queue.lock() {
+    for item in queue.iter_mut() {
+      if item.status == QueuedDownloadStatus::Failed {
+        item.status = QueuedDownloadStatus::Queued;
+        item.error = None;
+      }
+    }
+    write_download_queue(&queue);
+  }
+  ensure_download_worker(&app);
+}
+
+/// Drops an item from the queue. A download that is currently running has to
+/// finish or fail first.
+#[tauri::command]
+fn remove_queued_download(id: String, state: tauri::State<AppState>) -> Result<(), String> {
+  let mut queue = state.download_queue.lock().map_err(|e| e.to_string())?;
+  if queue
+    .iter()
+    .any(|item| item.id == id && item.status == QueuedDownloadStatus::Downloading)
+  {
+    return Err("That download is in progress.".to_string());
+  }
+  queue.retain(|item| item.id != id);
+  write_download_queue(&queue);
+  Ok(())
+}
+
+/// How long a fetched remote manifest is reused before hitting the network again.
+const MANIFEST_CACHE_TTL_SECS: u64 = 15 * 60;
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct RemoteManifest {
+  #[serde(default)]
+  versions: Vec<RemoteVersion>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RemoteVersion {
+  version: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  release_date: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  url: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  size: Option<u64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  sha256: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  notes: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  changelog_url: Option<String>,
+  /// Per-file listing of the version's payload, used to size differential
+  /// updates. Paths are relative to the install folder.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  files: Vec<RemoteFile>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct RemoteFile {
+  path: String,
+  size: u64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  sha256: Option<String>,
+  /// Bytes actually transferred for this file when it is served compressed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  compressed_size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedManifest {
+  url: String,
+  fetched_at: u64,
+  manifest: RemoteManifest,
+}
+
+/// Splits a version the same way the frontend does: each dot-separated segment
+/// keeps only its digits, and missing segments count as zero.
+fn parse_version(value: &str) -> Vec<u64> {
+  value
+    .split('.')
+    .map(|segment| {
+      segment
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+    })
+    .collect()
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+  let left = parse_version(a);
+  let right = parse_version(b);
+  for index in 0..left.len().max(right.len()) {
+    let lhs = left.get(index).copied().unwrap_or(0);
+    let rhs = right.get(index).copied().unwrap_or(0);
+    match lhs.cmp(&rhs) {
+      Ordering::Equal => continue,
+      other => return other,
+    }
+  }
+  Ordering::Equal
+}
+
+fn manifest_cache_path(app_id: &str) -> Option<PathBuf> {
+  Some(
+    hub_cache_dir()?
+      .join("manifests")
+      .join(format!("{}.json", cache_key(app_id))),
+  )
+}
+
+/// Fetches an app's remote manifest, reusing the on-disk copy while it is
+/// fresh and falling back to a stale copy when the network request fails.
+fn fetch_remote_manifest(
+  client: &Client,
+  app_id: &str,
+  manifest_url: &str,
+) -> Result<RemoteManifest, String> {
+  let cache_path = manifest_cache_path(app_id);
+  let cached = cache_path
+    .as_ref()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice::<CachedManifest>(&data).ok())
+    .filter(|cached| cached.url == manifest_url);
+  if let Some(cached) = cached.as_ref() {
+    if unix_timestamp().saturating_sub(cached.fetched_at) < MANIFEST_CACHE_TTL_SECS {
+      return Ok(cached.manifest.clone());
+    }
+  }
+
+  let fetched = client
+    .get(manifest_url)
+    .send()
+    .and_then(|response| response.error_for_status())
+    .and_then(|response| response.bytes())
+    .map_err(|e| e.to_string())
+    .and_then(|data| serde_json::from_slice::<RemoteManifest>(&data).map_err(|e| e.to_string()));
+  match fetched {
+    Ok(manifest) => {
+      if let Some(path) = cache_path {
+        let entry = CachedManifest {
+          url: manifest_url.to_string(),
+          fetched_at: unix_timestamp(),
+          manifest: manifest.clone(),
+        };
+        if let Some(parent) = path.parent() {
+          let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(data) = serde_json::to_vec_pretty(&entry) {
+          let _ = std::fs::write(path, data);
+        }
+      }
+      Ok(manifest)
+    }
+    Err(error) => cached
+      .map(|cached| cached.manifest)
+      .ok_or_else(|| format!("Failed to fetch manifest: {}", error)),
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangelogEntry {
+  version: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  release_date: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  notes: Option<String>,
+}
+
+/// Loads the release notes a manifest links to. Published notes never change
+/// for a version, so they are cached without expiry.
+fn fetch_changelog_text(client: &Client, app_id: &str, version: &str, url: &str) -> Option<String> {
+  let cache_path = hub_cache_dir().map(|dir| {
+    dir
+      .join("changelogs")
+      .join(format!("{}-{}.md", cache_key(app_id), cache_key(version)))
+  });
+  if let Some(text) = cache_path
+    .as_ref()
+    .and_then(|path| std::fs::read_to_string(path).ok())
+  {
+    return Some(text);
+  }
+  let text = client
+    .get(url)
+    .send()
+    .and_then(|response| response.error_for_status())
+    .and_then(|response| response.text())
+    .ok()?;
+  if let Some(path) = cache_path {
+    if let Some(parent) = path.parent() {
+      let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(path, &text);
+  }
+  Some(text)
+}
+
+fn file_sha256(path: &Path) -> Option<String> {
+  let mut file = File::open(path).ok()?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher).ok()?;
+  Some(format!("{:x}", hasher.finalize()))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatePreview {
+  version: String,
+  installed_version: Option<String>,
+  added_files: usize,
+  changed_files: usize,
+  removed_files: usize,
+  /// Uncompressed size of everything that has to be written.
+  changed_bytes: u64,
+  /// What will actually be downloaded, using declared compressed sizes.
+  download_bytes: u64,
+  /// Set when some sizes were not declared in the manifest and had to be
+  /// estimated, so `download_bytes` may be high.
+  estimated: bool,
+  /// Copies of the files being replaced or removed, kept until the update
+  /// succeeds.
+  backup_bytes: u64,
+  /// Peak extra disk space the update needs: the download, the staged files
+  /// and the backup together.
+  temporary_bytes: u64,
+}
+
+/// Sizes an update to `version` (the newest in `remote_manifest` by default)
+/// against what is installed for `app_id`. When the manifest lists the
+/// version's files only changed files are counted; otherwise the full package
+/// is assumed to replace the whole install.
+#[tauri::command]
+fn preview_update(
+  app_id: String,
+  remote_manifest: RemoteManifest,
+  version: Option<String>,
+) -> Result<UpdatePreview, String> {
+  let installed = load_install_manifest(&app_id)
+    .ok_or_else(|| "No install manifest recorded for this app.".to_string())?;
+  let target = match version.as_deref() {
+    Some(version) => remote_manifest
+      .versions
+      .iter()
+      .find(|entry| compare_versions(&entry.version, version) == Ordering::Equal),
+    None => remote_manifest
+      .versions
+      .iter()
+      .max_by(|a, b| compare_versions(&a.version, &b.version)),
+  }
+  .ok_or_else(|| "That version is not in the manifest.".to_string())?;
+  let install_path = PathBuf::from(&installed.install_dir);
+
+  let mut preview = UpdatePreview {
+    version: target.version.clone(),
+    installed_version: installed.version.clone(),
+    added_files: 0,
+    changed_files: 0,
+    removed_files: 0,
+    changed_bytes: 0,
+    download_bytes: 0,
+    estimated: false,
+    backup_bytes: 0,
+    temporary_bytes: 0,
+  };
+  if target.files.is_empty() {
+    let current = directory_size(&install_path);
+    preview.changed_files = installed.files.len();
+    preview.changed_bytes = target.size.unwrap_or(current);
+    preview.download_bytes = preview.changed_bytes;
+    preview.estimated = true;
+    preview.backup_bytes = current;
+  } else {
+    let remote_paths: HashSet<String> = target
+      .files
+      .iter()
+      .map(|file| file.path.replace('\\', "/").to_ascii_lowercase())
+      .collect();
+    for file in &target.files {
+      // Paths come from the remote manifest, so they must not reach outside
+      // the install folder.
+      let local = resolve_install_exe(&install_path, &file.path)?;
+      let local_size = std::fs::metadata(&local).ok().map(|meta| meta.len());
+      let unchanged = local_size == Some(file.size)
+        && file.sha256.as_deref().is_none_or(|expected| {
+          file_sha256(&local).is_some_and(|actual| actual.eq_ignore_ascii_case(expected))
+        });
+      if unchanged {
+        continue;
+      }
+      match local_size {
+        Some(size) => {
+          preview.changed_files += 1;
+          preview.backup_bytes += size;
+        }
+        None => preview.added_files += 1,
+      }
+      preview.changed_bytes += file.size;
+      preview.download_bytes += file.compressed_size.unwrap_or_else(|| {
+        preview.estimated = true;
+        file.size
+      });
+    }
+    for relative in &installed.files {
+      if !remote_paths.contains(&relative.replace('\\', "/").to_ascii_lowercase()) {
+        preview.removed_files += 1;
+        preview.backup_bytes += std::fs::metadata(install_path.join(relative))
+          .map(|meta| meta.len())
+          .unwrap_or(0);
+      }
+    }
+  }
+  preview.temporary_bytes = preview.download_bytes + preview.changed_bytes + preview.backup_bytes;
+  Ok(preview)
+}
+
+/// Returns the release notes for every published version newer than
+/// `installed_version`, newest first. Versions without notes are still listed
+/// so the update dialog can show what it is skipping over.
+#[tauri::command]
+fn get_changelog(
+  app_id: String,
+  manifest_url: String,
+  installed_version: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<Vec<ChangelogEntry>, String> {
+  let client = shared_http_client(&state)?;
+  let manifest = fetch_remote_manifest(&client, &app_id, &manifest_url)?;
+  let mut versions: Vec<&RemoteVersion> = manifest
+    .versions
+    .iter()
+    .filter(|entry| {
+      installed_version
+        .as_deref()
+        .is_none_or(|installed| compare_versions(&entry.version, installed) == Ordering::Greater)
+    })
+    .collect();
+  versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+  Ok(
+    versions
+      .into_iter()
+      .map(|entry| ChangelogEntry {
+        version: entry.version.clone(),
+        release_date: entry.release_date.clone(),
+        notes: entry.notes.clone().or_else(|| {
+          entry
+            .changelog_url
+            .as_deref()
+            .and_then(|url| fetch_changelog_text(&client, &app_id, &entry.version, url))
+        }),
+      })
+      .collect(),
+  )
+}
+
+#[tauri::command]
+fn get_current_exe_path() -> Result<String, String> {
+  std::env::current_exe()
+    .map_err(|e| e.to_string())
+    .map(|path| path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_program_files_dir() -> Result<String, String> {
+  std::env::var("ProgramFiles").map_err(|e| e.to_string())
+}
+
+const PE_MACHINE_I386: u16 = 0x014c;
+const PE_MACHINE_AMD64: u16 = 0x8664;
+const PE_MACHINE_ARM64: u16 = 0xaa64;
+
+/// 32 or 64, from the machine field of an EXE's PE header.
+fn pe_bitness(path: &Path) -> Option<u32> {
+  let mut file = File::open(path).ok()?;
+  let mut header = [0u8; 64];
+  file.read_exact(&mut header).ok()?;
+  if &header[..2] != b"MZ" {
+    return None;
+  }
+  let offset = u32::from_le_bytes([header[60], header[61], header[62], header[63]]);
+  file.seek(SeekFrom::Start(u64::from(offset))).ok()?;
+  let mut signature = [0u8; 6];
+  file.read_exact(&mut signature).ok()?;
+  if &signature[..4] != b"PE\0\0" {
+    return None;
+  }
+  match u16::from_le_bytes([signature[4], signature[5]]) {
+    PE_MACHINE_I386 => Some(32),
+    PE_MACHINE_AMD64 | PE_MACHINE_ARM64 => Some(64),
+    _ => None,
+  }
+}
+
+/// Bitness of what an installer puts on disk: an MSI's platform from its
+/// summary information, or a plain EXE's from its PE header. Inno Setup and
+/// NSIS ship 64-bit apps in 32-bit installers, so theirs gives `None`.
+fn installer_bitness(path: &Path) -> Option<u32> {
+  match detect_installer_kind(path)? {
+    "msi" => {
+      let package = msi::open(path).ok()?;
+      let arch = package.summary_info().arch()?.to_ascii_lowercase();
+      Some(if matches!(arch.as_str(), "intel" | "arm") {
+        32
+      } else {
+        64
+      })
+    }
+    "exe" => pe_bitness(path),
+    _ => None,
+  }
+}
+
+/// 64 when Windows is 64-bit, even if the hub itself runs as a 32-bit
+/// process under WOW64.
+fn system_bitness() -> u32 {
+  let is_64 = |name: &str| std::env::var(name).is_ok_and(|arch| arch.ends_with("64"));
+  if cfg!(target_pointer_width = "64")
+    || is_64("PROCESSOR_ARCHITECTURE")
+    || is_64("PROCESSOR_ARCHITEW6432")
+  {
+    64
+  } else {
+    32
+  }
+}
+
+fn program_files_dir_for(bitness: u32) -> Result<String, String> {
+  let name = match (bitness, system_bitness()) {
+    (64, _) => "ProgramW6432",
+    (_, 64) => "ProgramFiles(x86)",
+    _ => "ProgramFiles",
+  };
+  std::env::var(name)
+    .or_else(|_| std::env::var("ProgramFiles"))
+    .map_err(|e| e.to_string())
+}
+
+/// The Program Files folder an installer of the given bitness (32 or 64)
+/// belongs in: `Program Files (x86)` for 32-bit installers on 64-bit Windows.
+#[tauri::command]
+fn get_program_files_dir_for(bitness: u32) -> Result<String, String> {
+  if bitness != 32 && bitness != 64 {
+    return Err("Bitness must be 32 or 64.".to_string());
+  }
+  program_files_dir_for(bitness)
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct BitnessCheck {
+  app_id: Option<String>,
+  /// `None` when the installer's bitness cannot be read from it.
+  installer: Option<u32>,
+  system: u32,
+  /// Where an installer of this bitness belongs.
+  program_files_dir: Option<String>,
+  warning: Option<String>,
+}
+
+fn check_bitness(app_id: Option<&str>, path: &Path, install_dir: Option<&Path>) -> BitnessCheck {
+  let installer = installer_bitness(path);
+  let system = system_bitness();
+  let program_files_dir = installer
+    .filter(|bits| *bits <= system)
+    .and_then(|bits| program_files_dir_for(bits).ok());
+  let lowercase = |path: &Path| PathBuf::from(path.to_string_lossy().to_lowercase());
+  let warning = match (installer, install_dir) {
+    (Some(64), _) if system == 32 => {
+      Some("This installer is 64-bit and cannot run on 32-bit Windows.".to_string())
+    }
+    (Some(bits), Some(dir)) if system == 64 => {
+      let other = program_files_dir_for(if bits == 64 { 32 } else { 64 }).ok();
+      other
+        .filter(|other| lowercase(dir).starts_with(lowercase(Path::new(other))))
+        .map(|other| {
+          format!(
+            "This installer is {}-bit but is installing into {}; {}-bit apps belong in {}.",
+            bits,
+            other,
+            bits,
+            program_files_dir
+              .as_deref()
+              .unwrap_or("the other Program Files folder")
+          )
+        })
+    }
+    _ => None,
+  };
+  BitnessCheck {
+    app_id: app_id.map(str::to_string),
+    installer,
+    system,
+    program_files_dir,
+    warning,
+  }
+}
+
+/// Compares an installer's bitness with the system's, and with the Program
+/// Files folder `install_dir` is in, if any.
+#[tauri::command]
+fn check_installer_bitness(path: String, install_dir: Option<String>) -> BitnessCheck {
+  check_bitness(
+    None,
+    Path::new(&path),
+    install_dir.as_deref().map(Path::new),
+  )
+}
+
+/// Refuses a 64-bit installer on 32-bit Windows, and emits
+/// `installer-bitness-mismatch` when the install folder is in the Program
+/// Files folder of the other bitness.
+fn ensure_bitness_fits(
+  app: &tauri::AppHandle,
+  app_id: Option<&str>,
+  path: &Path,
+  install_dir: Option<&Path>,
+) -> Result<(), String> {
+  let check = check_bitness(app_id, path, install_dir);
+  if check.installer.is_some_and(|bits| bits > check.system) {
+    return Err(check.warning.unwrap_or_default());
+  }
+  if check.warning.is_some() {
+    let _ = app.emit_all("installer-bitness-mismatch", check);
+  }
+  Ok(())
+}
+
+/// Checks every app with an update source for a newer version, installs it
+/// and exits; run by the scheduled update task.
+const UPDATE_APPS_FLAG: &str = "--update-apps";
+
+/// Where the unattended updater looks for new versions of an app.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct UpdateSource {
+  manifest_url: String,
+  app_name: String,
+}
+
+fn update_sources_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("update-sources.json"))
+}
+
+fn load_update_sources() -> HashMap<String, UpdateSource> {
+  update_sources_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+/// Sets or (with `None`) clears the manifest the scheduled updater checks for
+/// `app_id`.
+#[tauri::command]
+fn set_update_source(app_id: String, source: Option<UpdateSource>) -> Result<(), String> {
+  let path = update_sources_path().ok_or_else(|| "Missing app data dir.".to_string())?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let mut sources = load_update_sources();
+  match source {
+    Some(source) => sources.insert(app_id, source),
+    None => sources.remove(&app_id),
+  };
+  let data = serde_json::to_vec_pretty(&sources).map_err(|e| e.to_string())?;
+  std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn version_pins_path() -> Option<PathBuf> {
+  Some(hub_data_dir()?.join("version-pins.json"))
+}
+
+/// Versions apps are held at, keyed by app id.
+fn load_version_pins() -> HashMap<String, String> {
+  version_pins_path()
+    .and_then(|path| std::fs::read(path).ok())
+    .and_then(|data| serde_json::from_slice(&data).ok())
+    .unwrap_or_default()
+}
+
+fn write_version_pins(pins: &HashMap<String, String>) -> Result<(), String> {
+  let path = version_pins_path().ok_or_else(|| "Missing app data dir.".to_string())?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let data = serde_json::to_vec_pretty(pins).map_err(|e| e.to_string())?;
+  std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Holds `app_id` at `version`: the scheduled updater skips it and installs
+/// of any other version are refused unless they override the pin.
+#[tauri::command]
+fn set_version_pin(app_id: String, version: String) -> Result<(), String> {
+  let version = version.trim().to_string();
+  if version.is_empty() {
+    return Err("A version is required.".to_string());
+  }
+  let mut pins = load_version_pins();
+  pins.insert(app_id, version);
+  write_version_pins(&pins)
+}
+
+#[tauri::command]
+fn clear_version_pin(app_id: String) -> Result<(), String> {
+  let mut pins = load_version_pins();
+  if pins.remove(&app_id).is_some() {
+    write_version_pins(&pins)?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn get_version_pins() -> HashMap<String, String> {
+  load_version_pins()
+}
+
+/// Refuses to replace an installed, pinned app with an installer for another
+/// version unless `override_pin` is set. Installers whose version cannot be
+/// read count as another version.
+fn ensure_pin_allows(app_id: &str, installer: &Path, override_pin: bool) -> Result<(), String> {
+  if override_pin || load_install_manifest(app_id).is_none() {
+    return Ok(());
+  }
+  let Some(pinned) = load_version_pins().remove(app_id) else {
+    return Ok(());
+  };
+  match msi_product_version(installer) {
+    Some(version) if compare_versions(&version, &pinned) == Ordering::Equal => Ok(()),
+    Some(version) => Err(format!(
+      "{} is pinned to version {}. Installing {} needs an explicit override.",
+      app_id, pinned, version
+    )),
+    None => Err(format!(
+      "{} is pinned to version {}. Installing another version needs an explicit override.",
+      app_id, pinned
+    )),
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AvailableVersion {
+  version: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  release_date: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  size: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  sha256: Option<String>,
+}
+
+/// Lists every version published in the app's manifest, newest first, so a
+/// specific one can be installed. The manifest comes from `manifest_url` or,
+/// when that is omitted, the app's update source.
+#[tauri::command]
+fn list_available_versions(
+  app_id: String,
+  manifest_url: Option<String>,
+  state: tauri::State<AppState>,
+) -> Result<Vec<AvailableVersion>, String> {
+  let manifest_url = manifest_url
+    .or_else(|| {
+      load_update_sources()
+        .remove(&app_id)
+        .map(|source| source.manifest_url)
+    })
+    .ok_or_else(|| format!("No manifest is known for {}.", app_id))?;
+  let client = shared_http_client(&state)?;
+  let mut versions = fetch_remote_manifest(&client, &app_id, &manifest_url)?.versions;
+  versions.sort_by(|a, b| compare_versions(&b.version, &a.version));
+  versions.dedup_by(|a, b| a.version == b.version);
+  Ok(
+    versions
+      .into_iter()
+      .map(|entry| AvailableVersion {
+        version: entry.version,
+        release_date: entry.release_date,
+        url: entry.url,
+        size: entry.size,
+        sha256: entry.sha256,
+      })
+      .collect(),
+  )
+}
+
+/// Installs the newest version listed in the app's remote manifest when it is
+/// newer than the recorded install. Only MSI installs can be updated without
+/// a user present.
+fn update_app_unattended(
+  client: &Client,
+  prefs: &HubPreferences,
+  app_id: &str,
+  source: &UpdateSource,
+) -> Result<(), String> {
+  if load_version_pins().contains_key(app_id) {
+    return Ok(());
+  }
+  let installed = load_install_manifest(app_id).ok_or_else(|| "Not installed.".to_string())?;
+  if installed
+    .installer
+    .as_ref()
+    .is_some_and(|installer| installer.installer_type != "msi")
+  {
+    return Err("Only MSI installs can be updated unattended.".to_string());
+  }
+  let current = installed
+    .version
+    .as_deref()
+    .ok_or_else(|| "The installed version is unknown.".to_string())?;
+  let exe_name = installed
+    .exe_name
+    .as_deref()
+    .ok_or_else(|| "The app's executable was not recorded.".to_string())?;
+  let manifest = fetch_remote_manifest(client, app_id, &source.manifest_url)?;
+  let Some(latest) = manifest
+    .versions
+    .iter()
+    .max_by(|a, b| compare_versions(&a.version, &b.version))
+    .filter(|latest| compare_versions(&latest.version, current) == Ordering::Greater)
+  else {
+    return Ok(());
+  };
+  let url = latest
+    .url
+    .as_deref()
+    .ok_or_else(|| format!("Version {} has no download URL.", latest.version))?;
+  check_download_allowed(prefs, url)?;
+
+  let cache_dir = installer_cache_dir().ok_or("Missing local data dir")?;
+  std::fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+  let installer = cache_dir.join(installer_file_name(url));
+  let hash = download_to_file(client, url, &installer, latest.sha256.as_deref(), |_, _| {})?;
+  if let Some(expected) = latest.sha256.as_deref() {
+    if !hash.eq_ignore_ascii_case(expected.trim()) {
+      let _ = std::fs::remove_file(&installer);
+      return Err("Installer checksum mismatch.".to_string());
+    }
+  }
+  record_cached_installer(&installer, app_id, url, Some(&hash));
+  scan_before_install(prefs, &installer)?;
+  install_msi_files(
+    app_id,
+    &installer,
+    Path::new(&installed.install_dir),
+    exe_name,
+    &source.app_name,
+    false,
+    false,
+    IoThrottle::Low,
+    false,
+    |_, _| {},
+  )
+  .map(|_| ())
+  .map_err(|error| error.message)
+}
+
+/// Handles `--update-apps`, carrying on past failures and reporting them all.
+fn run_unattended_updates() -> Result<(), String> {
+  let prefs = load_hub_preferences();
+  let client = build_http_client(&prefs)?;
+  let failures: Vec<String> = load_update_sources()
+    .iter()
+    .filter_map(|(app_id, source)| {
+      update_app_unattended(&client, &prefs, app_id, source)
+        .err()
+        .map(|error| format!("{}: {}", app_id, error))
+    })
+    .collect();
+  if failures.is_empty() {
+    Ok(())
+  } else {
+    Err(failures.join("\n"))
+  }
+}
+
+const MAX_UPDATE_INTERVAL_HOURS: u32 = 24 * 30;
+#[cfg(target_os = "windows")]
+const UPDATE_TASK_NAME: &str = "EnderfallHubUpdate";
+#[cfg(target_os = "macos")]
+const UPDATE_AGENT_LABEL: &str = "com.enderfall.hub.update";
+#[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+const UPDATE_TIMER_UNIT: &str = "enderfall-hub-update";
+
+fn run_checked(command: &mut std::process::Command, action: &str) -> Result<(), String> {
+  let output = command.output().map_err(|e| format!("{}: {}", action, e))?;
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(format!(
+      "{}: {}",
+      action,
+      String::from_utf8_lossy(&output.stderr).trim()
+    ))
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn update_agent_path() -> Option<PathBuf> {
+  Some(
+    tauri::api::path::home_dir()?
+      .join("Library")
+      .join("LaunchAgents")
+      .join(format!("{}.plist", UPDATE_AGENT_LABEL)),
+  )
+}
+
+#[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+fn systemd_user_dir() -> Option<PathBuf> {
+  let config = std::env::var_os("XDG_CONFIG_HOME")
+    .map(PathBuf::from)
+    .or_else(|| Some(tauri::api::path::home_dir()?.join(".config")))?;
+  Some(config.join("systemd").join("user"))
+}
+
+/// Schedules `--update-apps` every `interval_hours` with the OS scheduler, so
+/// apps stay updated while the hub is closed: a Task Scheduler task on
+/// Windows, a launchd agent on macOS and a systemd user timer elsewhere.
+/// Registering again replaces the previous schedule.
+#[tauri::command]
+fn register_update_task(interval_hours: u32) -> Result<(), String> {
+  if !(1..=MAX_UPDATE_INTERVAL_HOURS).contains(&interval_hours) {
+    return Err(format!(
+      "The interval must be between 1 and {} hours.",
+      MAX_UPDATE_INTERVAL_HOURS
+    ));
+  }
+  let exe = get_current_exe_path()?;
+  #[cfg(target_os = "windows")]
+  {
+    let (schedule, modifier) = if interval_hours.is_multiple_of(24) {
+      ("DAILY", interval_hours / 24)
+    } else if interval_hours < 24 {
+      ("HOURLY", interval_hours)
+    } else {
+      return Err("Intervals over a day must be a whole number of days.".to_string());
+    };
+    run_checked(
+      std::process::Command::new("schtasks").args([
+        "/Create",
+        "/F",
+        "/TN",
+        UPDATE_TASK_NAME,
+        "/SC",
+        schedule,
+        "/MO",
+        &modifier.to_string(),
+        "/TR",
+        &format!("\"{}\" {}", exe, UPDATE_APPS_FLAG),
+      ]),
+      "Could not create the update task",
+    )
+  }
+  #[cfg(target_os = "macos")]
+  {
+    let path = update_agent_path().ok_or_else(|| "Missing home dir.".to_string())?;
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let escape = |value: &str| {
+      value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+    };
+    let plist = format!(
+      r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>{}</string>
+    <string>{}</string>
+  </array>
+  <key>StartInterval</key>
+  <integer>{}</integer>
+</dict>
+</plist>
+"#,
+      UPDATE_AGENT_LABEL,
+      escape(&exe),
+      UPDATE_APPS_FLAG,
+      u64::from(interval_hours) * 3600
+    );
+    let _ = std::process::Command::new("launchctl")
+      .arg("unload")
+      .arg(&path)
+      .output();
+    std::fs::write(&path, plist).map_err(|e| e.to_string())?;
+    run_checked(
+      std::process::Command::new("launchctl")
+        .args(["load", "-w"])
+        .arg(&path),
+      "Could not load the update agent",
+    )
+  }
+  #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+  {
+    let dir = systemd_user_dir().ok_or_else(|| "Missing home dir.".to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let service = format!(
+      r#"[Unit]
+Description=Enderfall Hub app updates
+
+[Service]
+Type=oneshot
+ExecStart="{}" {}
+"#,
+      exe, UPDATE_APPS_FLAG
+    );
+    let timer = format!(
+      r#"[Unit]
+Description=Run Enderfall Hub app updates periodically
+
+[Timer]
+OnBootSec=15min
+OnUnitActiveSec={}h
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+"#,
+      interval_hours
+    );
+    std::fs::write(dir.join(format!("{}.service", UPDATE_TIMER_UNIT)), service)
+      .map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{}.timer", UPDATE_TIMER_UNIT)), timer)
+      .map_err(|e| e.to_string())?;
+    run_checked(
+      std::process::Command::new("systemctl").args(["--user", "daemon-reload"]),
+      "Could not reload systemd",
+    )?;
+    run_checked(
+      std::process::Command::new("systemctl").args([
+        "--user",
+        "enable",
+        "--now",
+        &format!("{}.timer", UPDATE_TIMER_UNIT),
+      ]),
+      "Could not enable the update timer",
+    )
+  }
+}
+
+/// Removes the schedule `register_update_task` created; does nothing when
+/// none is registered.
+#[tauri::command]
+fn unregister_update_task() -> Result<(), String> {
+  #[cfg(target_os = "windows")]
+  {
+    let registered = std::process::Command::new("schtasks")
+      .args(["/Query", "/TN", UPDATE_TASK_NAME])
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .status()
+      .is_ok_and(|status| status.success());
+    if !registered {
+      return Ok(());
+    }
+    run_checked(
+      std::process::Command::new("schtasks").args(["/Delete", "/F", "/TN", UPDATE_TASK_NAME]),
+      "Could not remove the update task",
+    )
+  }
+  #[cfg(target_os = "macos")]
+  {
+    let Some(path) = update_agent_path().filter(|path| path.exists()) else {
+      return Ok(());
+    };
+    let _ = std::process::Command::new("launchctl")
+      .args(["unload", "-w"])
+      .arg(&path)
+      .output();
+    std::fs::remove_file(&path).map_err(|e| e.to_string())
+  }
+  #[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+  {
+    let Some(dir) = systemd_user_dir() else {
+      return Ok(());
+    };
+    let timer = dir.join(format!("{}.timer", UPDATE_TIMER_UNIT));
+    if !timer.exists() {
+      return Ok(());
+    }
+    let _ = std::process::Command::new("systemctl")
+      .args([
+        "--user",
+        "disable",
+        "--now",
+        &format!("{}.timer", UPDATE_TIMER_UNIT),
+      ])
+      .output();
+    std::fs::remove_file(&timer).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(dir.join(format!("{}.service", UPDATE_TIMER_UNIT)));
+    run_checked(
+      std::process::Command::new("systemctl").args(["--user", "daemon-reload"]),
+      "Could not reload systemd",
+    )
+  }
+}
+
+const HUB_PROTOCOL: &str = "enderfall";
+/// Removes the protocol registration and exits; meant for the hub's uninstaller.
+const UNREGISTER_PROTOCOL_FLAG: &str = "--unregister-protocol";
+/// `--install <app_id>` is the command-line form of `enderfall://install/<app_id>`.
+const INSTALL_APP_FLAG: &str = "--install";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DeepLink {
+  action: String,
+  app_id: String,
+  url: String,
+}
+
+fn is_valid_app_id(value: &str) -> bool {
+  !value.is_empty()
+    && value.len() <= 64
+    && value
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parses `enderfall://install/<app_id>`; anything else is ignored.
+fn parse_deep_link(arg: &str) -> Option<DeepLink> {
+  let url = arg.trim().trim_matches('"');
+  let (scheme, rest) = url.split_once("://")?;
+  if !scheme.eq_ignore_ascii_case(HUB_PROTOCOL) {
+    return None;
+  }
+  let rest = rest.split(['?', '#']).next().unwrap_or_default();
+  let mut segments = rest.split('/').filter(|segment| !segment.is_empty());
+  let action = segments.next()?.to_ascii_lowercase();
+  let app_id = segments.next()?;
+  if action != "install" || segments.next().is_some() || !is_valid_app_id(app_id) {
+    return None;
+  }
+  Some(DeepLink {
+    action,
+    app_id: app_id.to_string(),
+    url: url.to_string(),
+  })
+}
+
+/// Finds the first deep link in a forwarded argument list, either as an
+/// `enderfall://` URL or as `--install <app_id>`. Malformed entries are skipped.
+fn parse_deep_link_args(args: &[String]) -> Option<DeepLink> {
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    if arg == INSTALL_APP_FLAG {
+      match args.next() {
+        Some(app_id) if is_valid_app_id(app_id) => {
+          return Some(DeepLink {
+            action: "install".to_string(),
+            app_id: app_id.clone(),
+            url: format!("{}://install/{}", HUB_PROTOCOL, app_id),
+          });
+        }
+        _ => continue,
+      }
+    }
+    if let Some(link) = parse_deep_link(arg) {
+      return Some(link);
+    }
+  }
+  None
+}
+
+/// Returns the deep link a second instance would forward for `args`, or
+/// `None` when nothing in them is recognized.
+#[tauri::command]
+fn validate_deep_link_args(args: Vec<String>) -> Option<DeepLink> {
+  parse_deep_link_args(&args)
+}
+
+#[cfg(target_os = "windows")]
+fn reg_add(key: &str, name: Option<&str>, data: &str) -> Result<(), String> {
+  let mut command = std::process::Command::new("reg");
+  command.args(["add", key]);
+  match name {
+    Some(name) => command.args(["/v", name]),
+    None => command.arg("/ve"),
+  };
+  let status = command
+    .args(["/d", data, "/f"])
+    .stdout(Stdio::null())
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("Failed to write registry key {}.", key))
+  }
+}
+
+/// Points `enderfall://` links at this executable for the current user.
+#[tauri::command]
+fn register_hub_protocol() -> Result<(), String> {
+  #[cfg(target_os = "windows")]
+  {
+    let exe = get_current_exe_path()?;
+    let key = format!(r"HKCU\Software\Classes\{}", HUB_PROTOCOL);
+    reg_add(&key, None, "URL:Enderfall Protocol")?;
+    reg_add(&key, Some("URL Protocol"), "")?;
+    reg_add(
+      &format!(r"{}\DefaultIcon", key),
+      None,
+      &format!("\"{}\",0", exe),
+    )?;
+    reg_add(
+      &format!(r"{}\shell\open\command", key),
+      None,
+      &format!("\"{}\" \"%1\"", exe),
+    )
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    Err("Protocol registration is only supported on Windows.".to_string())
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn reg_delete(key: &str) -> Result<(), String> {
+  if !registry_key_exists(key) {
+    return Ok(());
+  }
+  let status = std::process::Command::new("reg")
+    .args(["delete", key, "/f"])
+    .stdout(Stdio::null())
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("Failed to remove registry key {}.", key))
+  }
+}
+
+#[cfg(target_os = "windows")]
+fn reg_delete_value(key: &str, name: &str) -> Result<(), String> {
+  let status = std::process::Command::new("reg")
+    .args(["delete", key, "/v", name, "/f"])
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .map_err(|e| e.to_string())?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!(
+      "Failed to remove registry value {}\\{}.",
+      key, name
+    ))
+  }
+}
+
+#[tauri::command]
+fn unregister_hub_protocol() -> Result<(), String> {
+  #[cfg(target_os = "windows")]
+  {
+    reg_delete(&format!(r"HKCU\Software\Classes\{}", HUB_PROTOCOL))?;
+  }
+  Ok(())
+}
+
+/// Must be passed to `wipe_all_hub_data` verbatim so a stray call can never
+/// erase the hub's data.
+const WIPE_CONFIRM_TOKEN: &str = "wipe-all-hub-data";
+
+#[derive(Debug, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct WipeSummary {
+  removed: Vec<String>,
+  /// Entries left in place because an installed app lives inside them.
+  kept: Vec<String>,
+  failed: Vec<String>,
+  bytes_freed: u64,
+  unregistered: Vec<String>,
+}
+
+/// Deletes everything the hub keeps in its data folder: preferences,
+/// manifests, caches, logs and per-app settings. Only direct children of the
+/// folder are touched, and installed apps are left for `uninstall_app` even if
+/// they were installed inside it.
+#[tauri::command]
+fn wipe_all_hub_data(
+  confirm_token: String,
+  unregister_handlers: Option<bool>,
+  state: tauri::State<AppState>,
+) -> Result<WipeSummary, String> {
+  if confirm_token != WIPE_CONFIRM_TOKEN {
+    return Err("Confirmation token does not match.".to_string());
+  }
+  let root = hub_data_dir().ok_or_else(|| "Hub data folder is unavailable.".to_string())?;
+  let mut summary = WipeSummary::default();
+
+  if unregister_handlers.unwrap_or(false) {
+    #[cfg(target_os = "windows")]
+    {
+      for app_id in load_failed_installs().keys() {
+        let name = retry_run_once_name(app_id);
+        if registry_value(RUN_ONCE_KEY, &name).is_some()
+          && reg_delete_value(RUN_ONCE_KEY, &name).is_ok()
+        {
+          summary
+            .unregistered
+            .push(format!(r"{}\{}", RUN_ONCE_KEY, name));
+        }
+      }
+      let protocol_key = format!(r"HKCU\Software\Classes\{}", HUB_PROTOCOL);
+      if registry_key_exists(&protocol_key) && reg_delete(&protocol_key).is_ok() {
+        summary.unregistered.push(protocol_key);
+      }
     }
   }
 
-  if let Ok(appdata) = std::env::var("APPDATA") {
-    let start_menu = PathBuf::from(appdata)
-      .join("Microsoft")
-      .join("Windows")
-      .join("Start Menu")
-      .join("Programs")
-      .join("Enderfall")
-      .join(format!("{}.lnk", app_name));
-    if start_menu.exists() {
-      let _ = std::fs::remove_file(start_menu);
+  // Read before the manifests themselves are deleted.
+  let install_dirs: Vec<PathBuf> = load_all_install_manifests()
+    .iter()
+    .map(|manifest| canonicalize_lenient(Path::new(&manifest.install_dir)))
+    .collect();
+  let root = canonicalize_lenient(&root);
+  let Ok(entries) = std::fs::read_dir(&root) else {
+    return Ok(summary);
+  };
+  for entry in entries.flatten() {
+    let path = entry.path();
+    let label = path.to_string_lossy().to_string();
+    if install_dirs.iter().any(|dir| dir.starts_with(&path)) {
+      summary.kept.push(label);
+      continue;
+    }
+    let Ok(meta) = std::fs::symlink_metadata(&path) else {
+      continue;
+    };
+    let size = directory_size(&path);
+    let result = if meta.file_type().is_symlink() {
+      // Remove the link only, never what it points at.
+      std::fs::remove_file(&path).or_else(|_| std::fs::remove_dir(&path))
+    } else if meta.is_dir() {
+      std::fs::remove_dir_all(&path)
+    } else {
+      std::fs::remove_file(&path)
+    };
+    match result {
+      Ok(()) => {
+        summary.bytes_freed += size;
+        summary.removed.push(label);
+      }
+      Err(error) => summary.failed.push(format!("{}: {}", label, error)),
     }
   }
 
-  Ok(())
-}
-
-#[tauri::command]
-fn install_msi_payload(
-  window: tauri::Window,
-  app_id: String,
-  installer_path: String,
-  install_dir: String,
-  exe_name: String,
-  app_name: String,
-  create_desktop_shortcut: bool,
-  create_start_menu_shortcut: bool,
-) -> Result<(), String> {
-  let installer = PathBuf::from(&installer_path);
-  if !installer.exists() {
-    return Err("Installer not found.".to_string());
+  if let Ok(mut prefs) = state.prefs.lock() {
+    *prefs = HubPreferences::default();
   }
-  let install_path = PathBuf::from(&install_dir);
-  std::fs::create_dir_all(&install_path).map_err(|e| e.to_string())?;
+  if let Ok(mut queue) = state.download_queue.lock() {
+    queue.clear();
+  }
+  if let Ok(mut client) = state.http_client.lock() {
+    *client = None;
+  }
+  Ok(summary)
+}
 
-  let _ = window.emit(
-    "installer-progress",
-    serde_json::json!({ "appId": app_id, "progress": 0.1 }),
-  );
+#[cfg(target_os = "windows")]
+const UNINSTALL_KEY_ROOT: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Uninstall";
+/// Prefix of the Uninstall subkeys the hub creates, so they can be told apart
+/// from other software's entries.
+#[cfg(target_os = "windows")]
+const UNINSTALL_KEY_PREFIX: &str = "Enderfall.";
+/// Runs the uninstall for one app (by id) and exits; used as the
+/// `UninstallString` of the hub's Apps & features entries.
+const UNINSTALL_APP_FLAG: &str = "--uninstall-app";
 
-  let mut extractor = MsiExtractor::from_path(&installer).map_err(|e| e.to_string())?;
-  extractor.to(&install_path);
+#[cfg(target_os = "windows")]
+fn uninstall_command(app_id: &str) -> Result<String, String> {
+  Ok(format!(
+    "\"{}\" {} \"{}\"",
+    get_current_exe_path()?,
+    UNINSTALL_APP_FLAG,
+    app_id
+  ))
+}
 
-  let _ = window.emit(
-    "installer-progress",
-    serde_json::json!({ "appId": app_id, "progress": 0.85 }),
+/// Lists an app in Apps & features for the current user and returns the key.
+#[cfg(target_os = "windows")]
+fn register_uninstall_entry(
+  app_id: &str,
+  app_name: &str,
+  install_path: &Path,
+  exe_path: &Path,
+  version: Option<&str>,
+) -> Result<String, String> {
+  let key = format!(
+    r"{}\{}{}",
+    UNINSTALL_KEY_ROOT,
+    UNINSTALL_KEY_PREFIX,
+    cache_key(app_id)
   );
-
-  let exe_path = install_path.join(&exe_name);
-  if create_desktop_shortcut {
-    if let Some(desktop) = tauri::api::path::desktop_dir() {
-      let shortcut = desktop.join(format!("{}.lnk", app_name));
-      create_shortcut(&shortcut, &exe_path, &install_path)?;
-    }
-  }
-  if create_start_menu_shortcut {
-    if let Ok(appdata) = std::env::var("APPDATA") {
-      let start_menu = PathBuf::from(appdata)
-        .join("Microsoft")
-        .join("Windows")
-        .join("Start Menu")
-        .join("Programs")
-        .join("Enderfall");
-      let shortcut = start_menu.join(format!("{}.lnk", app_name));
-      create_shortcut(&shortcut, &exe_path, &install_path)?;
-    }
+  reg_add(&key, Some("DisplayName"), app_name)?;
+  reg_add(&key, Some("Publisher"), "Enderfall")?;
+  reg_add(
+    &key,
+    Some("InstallLocation"),
+    &install_path.to_string_lossy(),
+  )?;
+  reg_add(&key, Some("DisplayIcon"), &exe_path.to_string_lossy())?;
+  reg_add(&key, Some("UninstallString"), &uninstall_command(app_id)?)?;
+  reg_add(&key, Some("EnderfallAppId"), app_id)?;
+  if let Some(version) = version {
+    reg_add(&key, Some("DisplayVersion"), version)?;
   }
+  Ok(key)
+}
 
-  let _ = window.emit(
-    "installer-progress",
-    serde_json::json!({ "appId": app_id, "progress": 1.0 }),
-  );
+/// Full names of the immediate subkeys of `key`, as printed by `reg query`.
+#[cfg(target_os = "windows")]
+fn registry_subkeys(key: &str) -> Vec<String> {
+  let Ok(output) = std::process::Command::new("reg")
+    .args(["query", key])
+    .output()
+  else {
+    return Vec::new();
+  };
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .map(str::trim)
+    .filter(|line| line.starts_with("HKEY_") && !line.eq_ignore_ascii_case(key))
+    .map(str::to_string)
+    .collect()
+}
 
-  Ok(())
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UninstallEntryCheck {
+  key: String,
+  app_id: Option<String>,
+  install_location: Option<String>,
+  /// "ok", "repaired" (paths rewritten), "removed" (the app is gone) or
+  /// "missing" (the app is gone but the user kept the entry).
+  status: &'static str,
 }
 
+/// Walks the hub's Apps & features entries. Entries whose app moved (per its
+/// install manifest) get their paths rewritten, entries pointing at an older
+/// hub executable are updated, and entries for apps that no longer exist are
+/// removed along with their manifest once the user confirms.
 #[tauri::command]
-fn download_installer(
-  window: tauri::Window,
-  app_id: String,
-  url: String,
-  destination_dir: String,
-) -> Result<String, String> {
-  let dest_dir = PathBuf::from(&destination_dir);
-  std::fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+fn verify_uninstall_entries() -> Result<Vec<UninstallEntryCheck>, String> {
+  #[cfg(target_os = "windows")]
+  {
+    let mut checks = Vec::new();
+    for key in registry_subkeys(UNINSTALL_KEY_ROOT) {
+      let is_hub_entry = key
+        .rsplit('\\')
+        .next()
+        .is_some_and(|name| name.starts_with(UNINSTALL_KEY_PREFIX));
+      if !is_hub_entry {
+        continue;
+      }
+      let app_id = registry_value(&key, "EnderfallAppId");
+      let install_location = registry_value(&key, "InstallLocation");
+      let manifest = app_id.as_deref().and_then(load_install_manifest);
+      let located = install_location
+        .as_deref()
+        .is_some_and(|location| Path::new(location).is_dir());
+      let moved_to = manifest
+        .as_ref()
+        .map(|manifest| manifest.install_dir.clone())
+        .filter(|dir| Path::new(dir).is_dir() && Some(dir) != install_location.as_ref());
 
-  let file_name = url
-    .split('/')
-    .last()
-    .filter(|name| !name.is_empty())
-    .unwrap_or("installer.bin");
-  let destination = dest_dir.join(file_name);
+      if !located && moved_to.is_none() {
+        let name = registry_value(&key, "DisplayName").unwrap_or_else(|| key.clone());
+        let question = format!(
+          "{} is no longer installed{}.\n\nRemove it from Apps & features?",
+          name,
+          install_location
+            .as_deref()
+            .map(|location| format!(" at {}", location))
+            .unwrap_or_default()
+        );
+        if message_box(&question, MB_YESNO | MB_ICONQUESTION) != IDYES {
+          checks.push(UninstallEntryCheck {
+            key,
+            app_id,
+            install_location,
+            status: "missing",
+          });
+          continue;
+        }
+        reg_delete(&key)?;
+        if let Some(app_id) = app_id.as_deref() {
+          forget_install(app_id);
+        }
+        checks.push(UninstallEntryCheck {
+          key,
+          app_id,
+          install_location,
+          status: "removed",
+        });
+        continue;
+      }
 
-  let client = Client::new();
-  let mut response = client.get(&url).send().map_err(|e| e.to_string())?;
-  if !response.status().is_success() {
-    return Err(format!("Failed to download installer: {}", response.status()));
+      let mut repaired = false;
+      let mut location = install_location.clone();
+      if let (Some(dir), false) = (moved_to, located) {
+        reg_add(&key, Some("InstallLocation"), &dir)?;
+        if let Some(exe_name) = manifest
+          .as_ref()
+          .and_then(|manifest| manifest.exe_name.as_deref())
+        {
+          let icon = Path::new(&dir).join(exe_name);
+          reg_add(&key, Some("DisplayIcon"), &icon.to_string_lossy())?;
+        }
+        location = Some(dir);
+        repaired = true;
+      }
+      if let Some(app_id) = app_id.as_deref() {
+        let expected = uninstall_command(app_id)?;
+        if registry_value(&key, "UninstallString").as_deref() != Some(expected.as_str()) {
+          reg_add(&key, Some("UninstallString"), &expected)?;
+          repaired = true;
+        }
+      }
+      checks.push(UninstallEntryCheck {
+        key,
+        app_id,
+        install_location: location,
+        status: if repaired { "repaired" } else { "ok" },
+      });
+    }
+    Ok(checks)
   }
-  let total = response.content_length().unwrap_or(0);
-  let mut output = File::create(&destination).map_err(|e| e.to_string())?;
-  let mut copied: u64 = 0;
-  let mut buffer = [0u8; 1024 * 256];
+  #[cfg(not(target_os = "windows"))]
+  {
+    Err("Apps & features entries only exist on Windows.".to_string())
+  }
+}
 
-  loop {
-    let read = response.read(&mut buffer).map_err(|e| e.to_string())?;
-    if read == 0 {
-      break;
-    }
-    output.write_all(&buffer[..read]).map_err(|e| e.to_string())?;
-    copied += read as u64;
-    if total > 0 {
-      let progress = (copied as f64 / total as f64).min(1.0);
-      let _ = window.emit(
-        "installer-progress",
-        serde_json::json!({
-          "appId": app_id,
-          "progress": progress,
-        }),
-      );
+/// Looks for something to show a tray icon in. Windows and macOS always have
+/// one; on Linux the icon is an AppIndicator, which needs a StatusNotifier
+/// host on the session bus. If the bus cannot be asked, the tray is assumed
+/// to work.
+fn tray_host_available() -> bool {
+  if !cfg!(feature = "system-tray") {
+    return false;
+  }
+  if cfg!(any(target_os = "windows", target_os = "macos")) {
+    return true;
+  }
+  let output = std::process::Command::new("dbus-send")
+    .args([
+      "--session",
+      "--print-reply",
+      "--dest=org.freedesktop.DBus",
+      "/org/freedesktop/DBus",
+      "org.freedesktop.DBus.NameHasOwner",
+      "string:org.kde.StatusNotifierWatcher",
+    ])
+    .output();
+  match output {
+    Ok(output) if output.status.success() => {
+      String::from_utf8_lossy(&output.stdout).contains("boolean true")
     }
+    _ => true,
   }
-
-  output.flush().map_err(|e| e.to_string())?;
-
-  Ok(destination.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-fn get_current_exe_path() -> Result<String, String> {
-  std::env::current_exe()
-    .map_err(|e| e.to_string())
-    .map(|path| path.to_string_lossy().to_string())
+fn is_tray_available(state: tauri::State<AppState>) -> bool {
+  state.tray_available
 }
 
+/// Skips the single-instance handoff entirely. The plugin's lock is a named
+/// mutex (a DBus name on Linux) that the OS releases when a hub exits or
+/// crashes, so only a hub that is still running but hung can hold it; this
+/// flag is the recovery for that case.
+#[cfg(not(debug_assertions))]
+const FORCE_NEW_INSTANCE_FLAG: &str = "--force-new-instance";
+
+/// Client id the Edge WebView2 Runtime registers under EdgeUpdate.
+#[cfg(target_os = "windows")]
+const WEBVIEW2_CLIENT_ID: &str = "{F3017226-FE2A-4295-8BDF-00C3A9A7E4C5}";
+/// Microsoft's Evergreen Bootstrapper, which installs the current runtime.
+#[cfg(target_os = "windows")]
+const WEBVIEW2_BOOTSTRAPPER_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
+
+/// The installed WebView2 Runtime version, read from the keys its installer
+/// writes for per-machine (64- and 32-bit) and per-user installs.
 #[tauri::command]
-fn get_program_files_dir() -> Result<String, String> {
-  std::env::var("ProgramFiles").map_err(|e| e.to_string())
+fn get_webview2_version() -> Option<String> {
+  #[cfg(target_os = "windows")]
+  {
+    [
+      r"HKLM\SOFTWARE\WOW6432Node\Microsoft\EdgeUpdate\Clients",
+      r"HKLM\SOFTWARE\Microsoft\EdgeUpdate\Clients",
+      r"HKCU\Software\Microsoft\EdgeUpdate\Clients",
+    ]
+    .iter()
+    .filter_map(|key| registry_value(&format!(r"{}\{}", key, WEBVIEW2_CLIENT_ID), "pv"))
+    .find(|version| !version.is_empty() && version != "0.0.0.0")
+  }
+  #[cfg(not(target_os = "windows"))]
+  {
+    None
+  }
+}
+
+#[cfg(target_os = "windows")]
+const MB_YESNO: u32 = 0x4;
+#[cfg(target_os = "windows")]
+const MB_ICONQUESTION: u32 = 0x20;
+#[cfg(target_os = "windows")]
+const IDYES: i32 = 6;
+
+/// Shows a native message box, since without WebView2 there is no window to
+/// show anything in. Returns the id of the button pressed.
+#[cfg(target_os = "windows")]
+fn message_box(text: &str, flags: u32) -> i32 {
+  #[link(name = "user32")]
+  extern "system" {
+    fn MessageBoxW(
+      window: *mut std::ffi::c_void,
+      text: *const u16,
+      caption: *const u16,
+      flags: u32,
+    ) -> i32;
+  }
+  let text: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+  let caption: Vec<u16> = "Enderfall Hub".encode_utf16().chain(Some(0)).collect();
+  // SAFETY: both strings are NUL-terminated and a null owner is allowed.
+  unsafe { MessageBoxW(std::ptr::null_mut(), text.as_ptr(), caption.as_ptr(), flags) }
+}
+
+/// Makes sure the WebView2 Runtime is installed before any window is created.
+/// When it is missing and the user agrees, downloads the Evergreen
+/// Bootstrapper and runs it silently.
+#[cfg(target_os = "windows")]
+fn ensure_webview2() -> Result<(), String> {
+  if get_webview2_version().is_some() {
+    return Ok(());
+  }
+  let answer = message_box(
+    "Enderfall Hub needs the Microsoft Edge WebView2 Runtime, which is not installed.\n\nDownload and install it now?",
+    MB_YESNO | MB_ICONQUESTION,
+  );
+  if answer != IDYES {
+    return Err(
+      "Enderfall Hub cannot start without the Microsoft Edge WebView2 Runtime.".to_string(),
+    );
+  }
+  let client = build_http_client(&load_hub_preferences())?;
+  let cache_dir = installer_cache_dir().ok_or("Missing local data dir")?;
+  std::fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+  let bootstrapper = cache_dir.join("MicrosoftEdgeWebview2Setup.exe");
+  download_to_file(
+    &client,
+    WEBVIEW2_BOOTSTRAPPER_URL,
+    &bootstrapper,
+    None,
+    |_, _| {},
+  )?;
+  check_installer_integrity(&bootstrapper)?;
+  let status = std::process::Command::new(&bootstrapper)
+    .args(["/silent", "/install"])
+    .status()
+    .map_err(|e| e.to_string());
+  let _ = std::fs::remove_file(&bootstrapper);
+  let status = status?;
+  if !status.success() || get_webview2_version().is_none() {
+    return Err(format!(
+      "The WebView2 Runtime could not be installed (exit code {:?}).",
+      status.code()
+    ));
+  }
+  Ok(())
 }
 
 fn main() {
+  if std::env::args().any(|arg| arg == UNREGISTER_PROTOCOL_FLAG) {
+    if let Err(error) = unregister_hub_protocol() {
+      write_log("error", &error);
+    }
+    return;
+  }
+  let args: Vec<String> = std::env::args().collect();
+  if let Some(index) = args.iter().position(|arg| arg == RETRY_INSTALL_FLAG) {
+    let result = args
+      .get(index + 1)
+      .ok_or_else(|| format!("{} needs an app id.", RETRY_INSTALL_FLAG))
+      .and_then(|app_id| retry_install_from_args(app_id));
+    if let Err(error) = result {
+      write_log("error", &error);
+      std::process::exit(1);
+    }
+    return;
+  }
+  if args.iter().any(|arg| arg == UPDATE_APPS_FLAG) {
+    if let Err(error) = run_unattended_updates() {
+      write_log("error", &error);
+      std::process::exit(1);
+    }
+    return;
+  }
+  if let Some(index) = args.iter().position(|arg| arg == UNINSTALL_APP_FLAG) {
+    match args.get(index + 1) {
+      Some(app_id) => {
+        if let Err(error) = uninstall_from_manifest(app_id) {
+          write_log("error", &error);
+        }
+      }
+      None => write_log("error", &format!("{} needs an app id.", UNINSTALL_APP_FLAG)),
+    }
+    return;
+  }
+  #[cfg(target_os = "windows")]
+  if let Err(error) = ensure_webview2() {
+    const MB_ICONERROR: u32 = 0x10;
+    message_box(&error, MB_ICONERROR);
+    std::process::exit(1);
+  }
+
+  let preferences_recovery = recover_hub_preferences();
+  let prefs = load_hub_preferences();
+  #[cfg(target_os = "windows")]
+  set_default_webview_background(resolved_theme(None, &prefs));
   let builder = tauri::Builder::default().manage(AppState {
-    prefs: Mutex::new(load_hub_preferences()),
+    prefs: Mutex::new(prefs),
+    exits: Mutex::new(HashMap::new()),
+    active_downloads: Mutex::new(HashSet::new()),
+    pending_deep_link: Mutex::new(parse_deep_link_args(&std::env::args().collect::<Vec<_>>())),
+    download_queue: Mutex::new(restore_download_queue()),
+    download_worker_running: Mutex::new(false),
+    running_apps: Mutex::new(Vec::new()),
+    cancelled_deferrals: Mutex::new(HashSet::new()),
+    http_client: Mutex::new(None),
+    preferences_recovery: Mutex::new(preferences_recovery),
+    install_slots: InstallSlots::default(),
+    tray_available: tray_host_available(),
   });
   #[cfg(not(debug_assertions))]
-  let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-    if let Some(window) = app.get_window("main") {
-      let _ = window.show();
-      let _ = window.set_focus();
+  let builder = if std::env::args().any(|arg| arg == FORCE_NEW_INSTANCE_FLAG) {
+    builder
+  } else {
+    builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+      if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+        if let Some(link) = parse_deep_link_args(&args) {
+          let _ = window.emit("deep-link", link);
+        }
+      }
+    }))
+  };
+  let builder = builder.on_page_load(|window, _payload| {
+    if window.label() != "main" {
+      return;
     }
-  }));
+    if let Some(batch) = interrupted_batch_install() {
+      let _ = window.emit("batch-resume-available", batch);
+    }
+    if let (_, Some(warning)) = read_hub_preferences() {
+      let _ = window.emit("preferences-warning", warning);
+    }
+    let state = window.state::<AppState>();
+    if !state.tray_available {
+      let _ = window.emit("tray-unavailable", ());
+    }
+    let prefs = current_preferences(&state);
+    apply_window_zoom(&window, page_zoom(&window, &prefs));
+    apply_window_theme(&window, resolved_theme(Some(&window), &prefs));
+    let recovery = state
+      .preferences_recovery
+      .lock()
+      .ok()
+      .and_then(|mut recovery| recovery.take());
+    if let Some(recovery) = recovery {
+      let event = if recovery.source.is_some() {
+        "preferences-recovered"
+      } else {
+        "preferences-reset"
+      };
+      let _ = window.emit(event, recovery);
+    }
+    let restored: Vec<QueuedDownload> = state
+      .download_queue
+      .lock()
+      .map(|queue| {
+        queue
+          .iter()
+          .filter(|item| !item.is_finished())
+          .cloned()
+          .collect()
+      })
+      .unwrap_or_default();
+    if !restored.is_empty() {
+      let _ = window.emit("downloads-restored", restored);
+    }
+    let link = state
+      .pending_deep_link
+      .lock()
+      .ok()
+      .and_then(|mut link| link.take());
+    if let Some(link) = link {
+      let _ = window.emit("deep-link", link);
+    }
+  });
   let builder = builder.setup(|app| {
+    if let Some(window) = app.get_window("main") {
+      let prefs = current_preferences(&app.state::<AppState>());
+      apply_default_window_size(&window, effective_window_scale(&window, &prefs));
+      apply_window_theme(&window, resolved_theme(Some(&window), &prefs));
+    }
     #[cfg(target_os = "windows")]
     apply_window_icon(app);
     Ok(())
@@ -489,7 +9147,7 @@ fn main() {
         match event.event() {
           #[cfg(feature = "system-tray")]
           tauri::WindowEvent::CloseRequested { api, .. } => {
-            if prefs.close_to_tray {
+            if prefs.close_to_tray && state.tray_available {
               let _ = window.hide();
               api.prevent_close();
             }
@@ -497,7 +9155,7 @@ fn main() {
           #[cfg(not(feature = "system-tray"))]
           tauri::WindowEvent::CloseRequested { .. } => {}
           tauri::WindowEvent::Resized(_) => {
-            if prefs.minimize_to_tray {
+            if prefs.minimize_to_tray && state.tray_available {
               #[cfg(feature = "system-tray")]
               {
                 if let Ok(true) = window.is_minimized() {
@@ -515,19 +9173,505 @@ fn main() {
   builder
     .invoke_handler(tauri::generate_handler![
       path_exists,
+      validate_exe_path,
+      same_volume,
+      is_reboot_pending,
+      get_machine_id,
+      register_hub_protocol,
+      unregister_hub_protocol,
+      normalize_install_dir,
+      validate_extraction_paths,
       copy_installer,
       launch_path,
+      launch_app,
+      get_launch_profile,
+      get_app_installer_type,
+      wipe_all_hub_data,
+      get_launch_stats,
+      get_install_size,
+      get_hub_stats,
+      get_certificate_pin,
+      preview_update,
+      download_and_reassemble,
+      set_update_source,
+      register_update_task,
+      unregister_update_task,
+      check_install_dir,
+      check_notification_permission,
+      request_notification_permission,
+      send_notification,
+      validate_deep_link_args,
+      get_install_fingerprint,
+      check_network_location,
+      set_window_scale,
+      detect_installer_type,
+      check_downloaded_file,
+      normalize_shortcut_name,
+      get_available_memory,
+      verify_cached_installer,
+      paths_exist,
+      relink_install,
+      is_tray_available,
+      set_theme,
+      get_program_files_dir_for,
+      check_installer_bitness,
+      get_telemetry_queue,
+      flush_telemetry,
+      check_install_space,
+      validate_extraction_links,
+      get_os_version,
+      check_os_version,
+      set_zoom,
+      zoom_in,
+      zoom_out,
+      cancel_deferred_work,
+      list_available_versions,
+      set_version_pin,
+      clear_version_pin,
+      get_version_pins,
+      get_webview2_version,
+      set_launch_profile,
+      get_last_exit,
+      scan_file,
       run_installer,
       run_dev_app,
       create_shortcuts,
       uninstall_app,
       install_msi_payload,
+      retry_install,
+      get_failed_install,
+      export_app_manifest,
+      verify_shortcuts,
+      verify_start_menu,
+      verify_install,
+      verify_uninstall_entries,
+      validate_download_url,
       download_installer,
+      download_and_install,
+      start_batch_install,
+      resume_batch_install,
+      get_interrupted_batch_install,
+      discard_batch_install,
+      queue_download,
+      get_download_queue,
+      resume_download_queue,
+      remove_queued_download,
+      get_changelog,
+      list_cached_installers,
+      delete_cached_installer,
+      set_window_icon,
       get_current_exe_path,
       get_program_files_dir,
       get_hub_preferences,
-      set_hub_preferences
+      set_hub_preferences,
+      check_preferences_access,
+      get_preferences_history
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// An empty folder under the system temp dir, unique to this test run.
+  fn scratch_dir(name: &str) -> PathBuf {
+    let unique = format!("enderfall-hub-test-{}-{}", name, std::process::id());
+    let dir = std::env::temp_dir().join(unique);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn install_exe_inside_folder_is_accepted() {
+    let dir = scratch_dir("exe-inside");
+    let exe = dir.join("bin").join("game.exe");
+    std::fs::create_dir_all(dir.join("bin")).unwrap();
+    std::fs::write(&exe, b"").unwrap();
+    let resolved = resolve_install_exe(&dir, "bin/game.exe").unwrap();
+    assert_eq!(resolved, canonicalize_lenient(&exe));
+    assert!(ensure_inside_install_dir(&dir, &exe).is_ok());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn absolute_install_exe_is_rejected() {
+    let dir = scratch_dir("exe-absolute");
+    let elsewhere = scratch_dir("exe-absolute-elsewhere");
+    let exe = elsewhere.join("game.exe");
+    assert!(resolve_install_exe(&dir, &exe.to_string_lossy()).is_err());
+    assert!(ensure_inside_install_dir(&dir, &exe).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_dir_all(&elsewhere);
+  }
+
+  #[test]
+  fn parent_traversal_in_install_exe_is_rejected() {
+    let dir = scratch_dir("exe-traversal");
+    assert!(resolve_install_exe(&dir, "../game.exe").is_err());
+    assert!(resolve_install_exe(&dir, "bin/../../game.exe").is_err());
+    assert!(ensure_inside_install_dir(&dir, &dir.join("..").join("game.exe")).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn zero_total_progress_is_indeterminate() {
+    assert_eq!(progress_fraction(0, 0), None);
+    assert_eq!(progress_fraction(4096, 0), None);
+  }
+
+  #[test]
+  fn huge_file_progress_stays_in_range() {
+    assert_eq!(progress_fraction(u64::MAX, u64::MAX), Some(1.0));
+    assert_eq!(progress_fraction(u64::MAX, u64::MAX / 2), Some(1.0));
+    let almost = progress_fraction(u64::MAX - 1, u64::MAX).unwrap();
+    assert!(almost.is_finite() && almost <= 1.0);
+    let start = progress_fraction(1, u64::MAX).unwrap();
+    assert!(start > 0.0 && start < 1e-18);
+    let half = progress_fraction(u64::MAX / 2, u64::MAX).unwrap();
+    assert!((half - 0.5).abs() < 1e-12);
+  }
+
+  #[test]
+  fn reserved_device_names_are_renamed() {
+    let name = |app_name: &str| shortcut_file_name(app_name).unwrap();
+    assert_eq!(name("CON"), "CON_.lnk");
+    assert_eq!(name("nul"), "nul_.lnk");
+    assert_eq!(name("CON.foo"), "CON_.foo.lnk");
+    assert_eq!(name("com1 .txt"), "com1_ .txt.lnk");
+    assert_eq!(name("Lpt9.backup.old"), "Lpt9_.backup.old.lnk");
+    assert_eq!(name("CONSOLE"), "CONSOLE.lnk");
+    assert_eq!(name("Aux Tools"), "Aux Tools.lnk");
+  }
+
+  #[test]
+  fn reserved_characters_are_replaced() {
+    let name = |app_name: &str| shortcut_file_name(app_name);
+    assert_eq!(name("Game: Deluxe?").as_deref(), Some("Game_ Deluxe_.lnk"));
+    assert_eq!(
+      name("a<b>c\"d/e\\f|g*h").as_deref(),
+      Some("a_b_c_d_e_f_g_h.lnk")
+    );
+    assert_eq!(name("tab\there").as_deref(), Some("tab_here.lnk"));
+    assert_eq!(name("  Launcher...  ").as_deref(), Some("Launcher.lnk"));
+    assert_eq!(name(" .. "), None);
+  }
+
+  /// A test CA and a server certificate it issued for
+  /// `downloads.enderfall.test`, both P-256 and valid until 2126.
+  const TEST_CA_PEM: &str = "
+-----BEGIN CERTIFICATE-----
+MIIBoDCCAUWgAwIBAgIUSnqMaLYfeKP4iBK/pSL4EelTiNcwCgYIKoZIzj0EAwIw
+HDEaMBgGA1UEAwwRRW5kZXJmYWxsIFRlc3QgQ0EwIBcNMjYxMDE2MDk0NTM5WhgP
+MjEyNjA5MjIwOTQ1MzlaMBwxGjAYBgNVBAMMEUVuZGVyZmFsbCBUZXN0IENBMFkw
+EwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEton+AB4YcNqAKnB8DKijhSdlmYem2lZB
+a7H2JIDRT1lqy8eDY+BUfd4mzngAuWsoGlQBskLkpkBIsAM7ItmkS6NjMGEwHQYD
+VR0OBBYEFAkUdhIjTydnTSxZSeDexiSdphKpMB8GA1UdIwQYMBaAFAkUdhIjTydn
+TSxZSeDexiSdphKpMA8GA1UdEwEB/wQFMAMBAf8wDgYDVR0PAQH/BAQDAgIEMAoG
+CCqGSM49BAMCA0kAMEYCIQCFskFswP4NV5VWflHC3o520LmUdK0jte22FN5guLfW
+aAIhAKX/adJlo60axuv1e1V6KIs/QEEXSCsIoTuK4Mi3fU+T
+-----END CERTIFICATE-----
+";
+  const TEST_SERVER_PEM: &str = "
+-----BEGIN CERTIFICATE-----
+MIIB2TCCAX+gAwIBAgIUf9HBC1zDm5xXkKH+UUaeUCkJni4wCgYIKoZIzj0EAwIw
+HDEaMBgGA1UEAwwRRW5kZXJmYWxsIFRlc3QgQ0EwIBcNMjYxMDE2MDk0NTM5WhgP
+MjEyNjA5MjIwOTQ1MzlaMCMxITAfBgNVBAMMGGRvd25sb2Fkcy5lbmRlcmZhbGwu
+dGVzdDBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABPGeCXOSG5BP8QaxbGhvVn3K
+Z3JiAJUNa7v2QIbRuAklpJSKzfwiGPfjvSnjKxOKorCMEsWlZcmt5V3R5ECchpCj
+gZUwgZIwCQYDVR0TBAIwADALBgNVHQ8EBAMCB4AwEwYDVR0lBAwwCgYIKwYBBQUH
+AwEwIwYDVR0RBBwwGoIYZG93bmxvYWRzLmVuZGVyZmFsbC50ZXN0MB0GA1UdDgQW
+BBSk1x/3/rtsrVggcvLhVvZrlynNHzAfBgNVHSMEGDAWgBQJFHYSI08nZ00sWUng
+3sYknaYSqTAKBggqhkjOPQQDAgNIADBFAiAHUq9JnudRigTO4BPhF7c3QxYEASho
++JmvnI0lefyYtgIhAMET2Iixy2IcBt4nEKcQDKAQDbYFKkybpk5/8tE3OR6b
+-----END CERTIFICATE-----
+";
+  /// Pins of the two keys, from `openssl x509 -pubkey -noout | openssl pkey
+  /// -pubin -outform der | openssl dgst -sha256 -binary | base64`.
+  const TEST_SERVER_PIN: &str = "SMgT+sWiDeN0AkE4QKsQQyFpAopuypwgA+y2xg7LeIA=";
+  const TEST_CA_PIN: &str = "kVuAqIzkSKJ6LsCTN44ZY8nUrGpimuf8WCFn6MOzxq8=";
+
+  fn pem_certificate(pem: &str) -> CertificateDer<'static> {
+    CertificateDer::from_pem_slice(pem.trim().as_bytes()).unwrap()
+  }
+
+  fn verify_test_server(
+    verifier: &PinnedCertVerifier,
+  ) -> Result<ServerCertVerified, rustls::Error> {
+    let server_name = ServerName::try_from("downloads.enderfall.test").unwrap();
+    let server = pem_certificate(TEST_SERVER_PEM);
+    verifier.verify_server_cert(&server, &[], &server_name, &[], UnixTime::now())
+  }
+
+  fn test_roots() -> rustls::RootCertStore {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add(pem_certificate(TEST_CA_PEM)).unwrap();
+    roots
+  }
+
+  #[test]
+  fn scrubbing_keeps_codes_and_plain_words() {
+    for message in [
+      "Installer exited with code Some(1603).",
+      "Update 1.2.3 failed: Access is denied. (os error 5)",
+      "HRESULT 0x80070005 while extracting",
+    ] {
+      assert_eq!(scrub_message_words(message, Some("alice")), message);
+    }
+  }
+
+  #[test]
+  fn scrubbing_replaces_paths_and_shares() {
+    assert_eq!(
+      scrub_message_words(r"Cannot open C:\Games\Hub\data.pak", None),
+      "Cannot open <path>"
+    );
+    assert_eq!(
+      scrub_message_words(r"Share \\fileserver\games is offline", None),
+      "Share <path> is offline"
+    );
+    assert_eq!(
+      scrub_message_words("Mapped //fileserver/games and %5C%5Cnas%5Chub", None),
+      "Mapped <path> and <path>"
+    );
+  }
+
+  #[test]
+  fn scrubbing_replaces_the_user_name() {
+    assert_eq!(
+      scrub_message_words("Profile ALICE-PC is locked", Some("alice")),
+      "Profile <user> is locked"
+    );
+  }
+
+  #[test]
+  fn scrubbing_replaces_addresses_and_hosts() {
+    assert_eq!(
+      scrub_message_words(
+        "connect to 10.0.0.12:443 failed (192.168.1.5) [fe80::1]:8080 ::1",
+        None
+      ),
+      "connect to <address> failed (<address>) <address> <address>"
+    );
+    assert_eq!(
+      scrub_message_words(
+        "mirror-01:8443 refused, try fs@SSL@443 or ops@example.com.",
+        None
+      ),
+      "<address> refused, try <address> or <address>."
+    );
+    assert_eq!(
+      scrub_message_words("dns error for cdn.example.com, retrying", None),
+      "dns error for <name>, retrying"
+    );
+  }
+
+  #[test]
+  fn scrubbing_replaces_file_names() {
+    assert_eq!(
+      scrub_message_words("'setup.exe' could not load d3dx9_43.dll.", None),
+      "'<name>' could not load <name>."
+    );
+  }
+
+  #[test]
+  fn scrubbed_messages_are_capped() {
+    let message = "word ".repeat(TELEMETRY_MESSAGE_MAX_CHARS);
+    assert_eq!(
+      scrub_message_words(&message, None).chars().count(),
+      TELEMETRY_MESSAGE_MAX_CHARS
+    );
+  }
+
+  #[cfg(unix)]
+  fn link(target: &Path, link: &Path) {
+    std::os::unix::fs::symlink(target, link).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn removing_a_folder_leaves_link_targets_outside_it() {
+    let root = scratch_dir("link-outside");
+    let outside = root.join("outside");
+    let install = root.join("install");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::create_dir_all(&install).unwrap();
+    std::fs::write(outside.join("save.dat"), b"keep").unwrap();
+    link(&outside, &install.join("saves"));
+    link(&outside.join("save.dat"), &install.join("save.dat"));
+
+    let summary = remove_install_dir(&install).unwrap();
+
+    assert!(!install.exists());
+    assert!(outside.join("save.dat").is_file());
+    assert_eq!(summary.removed, 3);
+    assert!(summary.failed.is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn link_loops_are_not_followed() {
+    let root = scratch_dir("link-loop");
+    let install = root.join("install");
+    std::fs::create_dir_all(install.join("bin")).unwrap();
+    std::fs::write(install.join("bin").join("game"), b"1234").unwrap();
+    link(&install, &install.join("bin").join("up"));
+    link(&install.join("b"), &install.join("a"));
+    link(&install.join("a"), &install.join("b"));
+
+    assert_eq!(
+      list_installed_files(&install),
+      ["a", "b", "bin/game", "bin/up"]
+    );
+    assert!(directory_size(&install) >= 4);
+    remove_install_dir(&install).unwrap();
+    assert!(!install.exists());
+    assert!(root.is_dir());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn dangling_links_are_removed() {
+    let root = scratch_dir("link-dangling");
+    let install = root.join("install");
+    std::fs::create_dir_all(&install).unwrap();
+    link(&root.join("missing"), &install.join("data"));
+
+    remove_install_dir(&install).unwrap();
+
+    assert!(std::fs::symlink_metadata(&install).is_err());
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn extraction_through_links_is_reported() {
+    let root = scratch_dir("link-extract");
+    let outside = root.join("outside");
+    let install = root.join("install");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::create_dir_all(install.join("bin")).unwrap();
+    link(&outside, &install.join("data"));
+    link(&root.join("missing"), &install.join("logs"));
+    let paths = [
+      install.join("bin").join("game"),
+      install.join("data").join("level1.pak"),
+      install.join("data").join("level2.pak"),
+      install.join("logs").join("first.log"),
+      install.join("..").join("outside").join("escape.dll"),
+    ];
+
+    let linked = linked_payload_paths(&paths, &install);
+
+    assert_eq!(
+      linked,
+      [
+        install.join("data").to_string_lossy().to_string(),
+        install.join("logs").to_string_lossy().to_string(),
+        paths[4].to_string_lossy().to_string(),
+      ]
+    );
+
+    let linked_install = root.join("linked-install");
+    link(&install, &linked_install);
+    assert_eq!(
+      linked_payload_paths(&[linked_install.join("bin").join("game")], &linked_install),
+      [linked_install.to_string_lossy().to_string()]
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  /// Writes an installer that prints the `config.txt` next to wherever it
+  /// runs.
+  #[cfg(unix)]
+  fn config_reading_installer(dir: &Path) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    let installer = dir.join("setup.sh");
+    std::fs::write(&installer, "#!/bin/sh\ncat config.txt\n").unwrap();
+    std::fs::set_permissions(&installer, std::fs::Permissions::from_mode(0o755)).unwrap();
+    installer
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn installer_finds_config_next_to_it_by_default() {
+    let root = scratch_dir("installer-cwd");
+    let installer = config_reading_installer(&root);
+    std::fs::write(root.join("config.txt"), "bundled").unwrap();
+
+    let working_dir = installer_working_dir(&installer, None).unwrap();
+    let output = installer_command(&installer, false, &[], &working_dir)
+      .output()
+      .unwrap();
+
+    assert_eq!(working_dir, root);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"bundled");
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn installer_uses_the_given_working_dir() {
+    let root = scratch_dir("installer-working-dir");
+    let installer = config_reading_installer(&root);
+    std::fs::write(root.join("config.txt"), "bundled").unwrap();
+    let config_dir = root.join("config");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    std::fs::write(config_dir.join("config.txt"), "custom").unwrap();
+
+    let working_dir = installer_working_dir(&installer, config_dir.to_str()).unwrap();
+    let output = installer_command(&installer, false, &[], &working_dir)
+      .output()
+      .unwrap();
+
+    assert_eq!(output.stdout, b"custom");
+    assert!(
+      installer_working_dir(&installer, Some(&root.join("missing").to_string_lossy())).is_err()
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+  }
+
+  #[test]
+  fn certificate_pin_matches_openssl() {
+    let server = pem_certificate(TEST_SERVER_PEM);
+    let ca = pem_certificate(TEST_CA_PEM);
+    assert_eq!(certificate_spki_sha256(&server).unwrap(), TEST_SERVER_PIN);
+    assert_eq!(certificate_spki_sha256(&ca).unwrap(), TEST_CA_PIN);
+    assert!(certificate_spki_sha256(b"not a certificate").is_err());
+  }
+
+  #[test]
+  fn pinned_verifier_accepts_the_pinned_key() {
+    let verifier = PinnedCertVerifier::new(test_roots(), false, TEST_SERVER_PIN).unwrap();
+    assert!(verify_test_server(&verifier).is_ok());
+  }
+
+  #[test]
+  fn pinned_verifier_rejects_another_key() {
+    let verifier = PinnedCertVerifier::new(test_roots(), false, TEST_CA_PIN).unwrap();
+    assert!(verify_test_server(&verifier).is_err());
+  }
+
+  #[test]
+  fn pinned_verifier_still_checks_the_chain() {
+    let untrusted = rustls::RootCertStore {
+      roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let verifier = PinnedCertVerifier::new(untrusted.clone(), false, TEST_SERVER_PIN).unwrap();
+    assert!(verify_test_server(&verifier).is_err());
+    // With verification turned off only the pin is left to check.
+    let verifier = PinnedCertVerifier::new(untrusted, true, TEST_SERVER_PIN).unwrap();
+    assert!(verify_test_server(&verifier).is_ok());
+  }
+}
@@ -41,6 +41,10 @@ type HubPreferences = {
   openOnStartup: boolean;
   closeToTray: boolean;
   minimizeToTray: boolean;
+  notificationsEnabled?: boolean;
+  scanDownloads?: boolean;
+  telemetryEnabled?: boolean;
+  telemetryEndpoint?: string;
 };
 
 
@@ -112,6 +116,11 @@ type InstallerAssetPattern = {
   type: "msi" | "exe";
 };
 
+type InstallerRecord = {
+  installerType: "msi" | "exe";
+  silentArgs: string[];
+};
+
 type AppInfo = {
   id: string;
 
@@ -153,10 +162,18 @@ type AppInfo = {
 
   installerType?: "msi" | "exe";
 
+  minOsVersion?: string;
+
 };
 
 
 
+// Messages to switch to when a combined download-and-install reaches its install phase.
+const installPhaseMessages: Record<string, string> = {
+  "Downloading installer...": "Installing...",
+  "Downloading update...": "Installing update...",
+};
+
 const themeOptions: { value: ThemeMode; label: string }[] = [
 
   { value: "system", label: "System (Default)" },
@@ -191,8 +208,14 @@ const getInstallerPatterns = (app: AppInfo): InstallerAssetPattern[] => {
   ];
 };
 
-const selectInstallerAsset = (app: AppInfo, release: GithubRelease) => {
-  const patterns = getInstallerPatterns(app);
+const selectInstallerAsset = (
+  app: AppInfo,
+  release: GithubRelease,
+  preferredType?: "msi" | "exe"
+) => {
+  const patterns = [...getInstallerPatterns(app)].sort(
+    (a, b) => Number(b.type === preferredType) - Number(a.type === preferredType)
+  );
   for (const entry of patterns) {
     const matcher = new RegExp(entry.pattern, "i");
     const installer = release.assets.find((asset) => matcher.test(asset.name));
@@ -212,6 +235,26 @@ const buildInstallerArgs = (app: AppInfo, installDir: string) => {
   return buildDefaultInstallerArgs(installDir);
 };
 
+const getKnownInstaller = async (appId: string) => {
+  try {
+    return await invoke<InstallerRecord | null>("get_app_installer_type", { appId });
+  } catch {
+    return null;
+  }
+};
+
+const resolveExeInstallerArgs = async (
+  appId: string,
+  installDir: string,
+  fallback: () => string[]
+) => {
+  const known = await getKnownInstaller(appId);
+  if (known?.installerType === "exe" && known.silentArgs.length > 0) {
+    return known.silentArgs.map((arg) => arg.replace("{installDir}", installDir));
+  }
+  return fallback();
+};
+
 
 
 const apps: AppInfo[] = [
@@ -1470,6 +1513,11 @@ const AppContent = () => {
     closeToTray: true,
     minimizeToTray: true,
   });
+  const [telemetryEndpointDraft, setTelemetryEndpointDraft] = useState("");
+
+  useEffect(() => {
+    setTelemetryEndpointDraft(hubPreferences.telemetryEndpoint ?? "");
+  }, [hubPreferences.telemetryEndpoint]);
 
   const sharedThemeUpdatedAtRef = useRef<number>(0);
   const sharedThemeApplyRef = useRef<ThemeMode | null>(null);
@@ -1499,6 +1547,10 @@ const AppContent = () => {
           openOnStartup: update.openOnStartup,
           closeToTray: update.closeToTray,
           minimizeToTray: update.minimizeToTray,
+          notificationsEnabled: update.notificationsEnabled,
+          scanDownloads: update.scanDownloads,
+          telemetryEnabled: update.telemetryEnabled,
+          telemetryEndpoint: update.telemetryEndpoint,
         },
       });
       setHubPreferences(prefs);
@@ -1507,6 +1559,16 @@ const AppContent = () => {
     }
   };
 
+  const enableNotifications = async () => {
+    if (!isTauri) return;
+    try {
+      await invoke<string>("request_notification_permission");
+      await loadHubPreferences();
+    } catch {
+      // Notifications fall back to in-app messages.
+    }
+  };
+
   useEffect(() => {
     void loadHubPreferences();
   }, []);
@@ -1523,6 +1585,17 @@ const AppContent = () => {
 
   const [installStatus, setInstallStatus] = useState<Record<string, boolean>>({});
 
+  const [versionPins, setVersionPins] = useState<Record<string, string>>({});
+
+  useEffect(() => {
+    if (!isTauri) return;
+    invoke<Record<string, string>>("get_version_pins")
+      .then(setVersionPins)
+      .catch(() => {
+        // Without pins every app is treated as unpinned.
+      });
+  }, []);
+
   const [devStatus, setDevStatus] = useState<Record<string, boolean>>({});
 
   const [installing, setInstalling] = useState<Record<string, boolean>>({});
@@ -1639,6 +1712,25 @@ const AppContent = () => {
     };
   }, []);
 
+  useEffect(() => {
+    if (!isTauri) return;
+    const handleZoomKeys = (event: KeyboardEvent) => {
+      if (!event.ctrlKey || event.altKey) return;
+      if (event.key === "+" || event.key === "=") {
+        void invoke("zoom_in").catch(() => undefined);
+      } else if (event.key === "-") {
+        void invoke("zoom_out").catch(() => undefined);
+      } else if (event.key === "0") {
+        void invoke("set_zoom", { level: 1 }).catch(() => undefined);
+      } else {
+        return;
+      }
+      event.preventDefault();
+    };
+    window.addEventListener("keydown", handleZoomKeys);
+    return () => window.removeEventListener("keydown", handleZoomKeys);
+  }, []);
+
   useEffect(() => {
     if (typeof window === "undefined") return;
 
@@ -1667,6 +1759,9 @@ const AppContent = () => {
 
     persistTheme("themeMode");
     persistTheme("appBrowserTheme");
+    const nativeTheme =
+      themeMode === "system" ? "system" : themeMode === "light" || themeMode === "plain-light" ? "light" : "dark";
+    invoke("set_theme", { theme: nativeTheme }).catch(() => undefined);
 
     applyResolvedTheme();
     if (themeMode !== "system") return;
@@ -1807,10 +1902,11 @@ const AppContent = () => {
           let selectedRelease: GithubRelease | undefined;
           let installer: GithubReleaseAsset | undefined;
           let installerType: "msi" | "exe" = app.installerType ?? "msi";
+          const known = isTauri ? await getKnownInstaller(app.id) : null;
 
           const pickInstaller = (entries: GithubRelease[]) => {
             for (const entry of entries) {
-              const selected = selectInstallerAsset(app, entry);
+              const selected = selectInstallerAsset(app, entry, known?.installerType);
               if (selected.installer) {
                 selectedRelease = entry;
                 installer = selected.installer;
@@ -2311,44 +2407,32 @@ const AppContent = () => {
 
     try {
 
-      const baseDir = await appDataDir();
-
-      const installersDir = await join(baseDir, "Enderfall", "Installers");
-
-      const installerPath = await invoke<string>("download_installer", {
-
-        appId: app.id,
-
-        url: update.installerUrl,
-
-        destinationDir: installersDir,
-
-      });
-
-      setInstallMessage((prev) => ({ ...prev, [app.id]: "Installing update..." }));
-
       const installerType = update.installerType ?? "exe";
 
       if (installerType === "exe") {
-        const args = buildInstallerArgs(app, installDir);
-        await invoke("run_installer", { path: installerPath, args });
+        const baseDir = await appDataDir();
+        const installersDir = await join(baseDir, "Enderfall", "Installers");
+        const installerPath = await invoke<string>("download_installer", {
+          appId: app.id,
+          url: update.installerUrl,
+          destinationDir: installersDir,
+        });
+        setInstallMessage((prev) => ({ ...prev, [app.id]: "Installing update..." }));
+        const args = await resolveExeInstallerArgs(app.id, installDir, () =>
+          buildInstallerArgs(app, installDir)
+        );
+        await invoke("run_installer", { path: installerPath, args, appId: app.id, installDir });
       } else {
-        await invoke("install_msi_payload", {
-
+        await invoke("download_and_install", {
           appId: app.id,
-
-          installerPath,
-
+          url: update.installerUrl,
           installDir,
-
           exeName: app.exeName,
-
           appName: app.name,
-
+          keepInstaller: false,
           createDesktopShortcut: false,
-
           createStartMenuShortcut: false,
-
+          minOsVersion: app.minOsVersion,
         });
       }
 
@@ -2406,44 +2490,37 @@ const AppContent = () => {
 
       const baseDir = await appDataDir();
 
-      const installersDir = await join(baseDir, "Enderfall", "Installers");
-
-      const installerPath = await invoke<string>("download_installer", {
-
-        appId: update.id,
-
-        url: update.installerUrl,
-
-        destinationDir: installersDir,
-
-      });
-
-      setInstallMessage((prev) => ({ ...prev, [update.id]: "Installing update..." }));
-
       const updateDir = await join(baseDir, "Enderfall", "AppBrowser", update.version);
 
       const installerType = update.installerType ?? "msi";
 
       if (installerType === "exe") {
-        const args = buildDefaultInstallerArgs(updateDir);
-        await invoke("run_installer", { path: installerPath, args });
+        const installersDir = await join(baseDir, "Enderfall", "Installers");
+        const installerPath = await invoke<string>("download_installer", {
+          appId: update.id,
+          url: update.installerUrl,
+          destinationDir: installersDir,
+        });
+        setInstallMessage((prev) => ({ ...prev, [update.id]: "Installing update..." }));
+        const args = await resolveExeInstallerArgs(update.id, updateDir, () =>
+          buildDefaultInstallerArgs(updateDir)
+        );
+        await invoke("run_installer", {
+          path: installerPath,
+          args,
+          appId: update.id,
+          installDir: updateDir,
+        });
       } else {
-        await invoke("install_msi_payload", {
-
+        await invoke("download_and_install", {
           appId: update.id,
-
-          installerPath,
-
+          url: update.installerUrl,
           installDir: updateDir,
-
           exeName,
-
           appName: "Enderfall Hub",
-
+          keepInstaller: false,
           createDesktopShortcut: false,
-
           createStartMenuShortcut: false,
-
         });
       }
 
@@ -2475,7 +2552,7 @@ const AppContent = () => {
 
     const unlistenPromise = listen("installer-progress", (event) => {
 
-      const payload = event.payload as { appId?: string; progress?: number };
+      const payload = event.payload as { appId?: string; progress?: number; phase?: string };
 
       if (!payload?.appId) return;
 
@@ -2487,6 +2564,14 @@ const AppContent = () => {
 
       }));
 
+      if (payload.phase === "install") {
+        const appId = payload.appId;
+        setInstallMessage((prev) => {
+          const installing = installPhaseMessages[prev[appId]];
+          return installing ? { ...prev, [appId]: installing } : prev;
+        });
+      }
+
     });
 
 
@@ -2508,31 +2593,20 @@ const AppContent = () => {
     const checkInstalled = async () => {
 
       const next: Record<string, boolean> = {};
-
-      for (const app of apps) {
-
-        const installPath = getResolvedInstallExePath(app, defaultInstallDirs[app.id]);
-
-        if (!installPath) {
-
-          next[app.id] = false;
-
-          continue;
-
-        }
-
-        try {
-
-          const exists = await invoke<boolean>("path_exists", { path: installPath });
-
-          next[app.id] = exists;
-
-        } catch {
-
-          next[app.id] = false;
-
-        }
-
+      const checks = apps.map((app) => ({
+        id: app.id,
+        path: getResolvedInstallExePath(app, defaultInstallDirs[app.id]),
+      }));
+      const paths = checks.flatMap((check) => (check.path ? [check.path] : []));
+      let results: boolean[] = [];
+      try {
+        results = await invoke<boolean[]>("paths_exist", { paths });
+      } catch {
+        // Treat every app as not installed.
+      }
+      let index = 0;
+      for (const check of checks) {
+        next[check.id] = check.path ? results[index++] ?? false : false;
       }
 
       setInstallStatus(next);
@@ -2648,7 +2722,7 @@ const AppContent = () => {
 
     if (!installPath) return;
 
-    await invoke("launch_path", { path: installPath });
+    await invoke("launch_app", { appId: app.id, path: installPath });
 
   };
 
@@ -2728,13 +2802,22 @@ const AppContent = () => {
 
     try {
 
-      await invoke("uninstall_app", { installDir, appName: app.name });
+      const removal = await invoke<{ rebootRequired: boolean }>("uninstall_app", {
+        installDir,
+        appName: app.name,
+        appId: app.id,
+      });
 
       localStorage.removeItem(`appbrowser-install-version-${app.id}`);
 
       localStorage.removeItem(`appbrowser-install-dir-${app.id}`);
 
-      setInstallMessage((prev) => ({ ...prev, [app.id]: "Uninstalled." }));
+      setInstallMessage((prev) => ({
+        ...prev,
+        [app.id]: removal.rebootRequired
+          ? "Uninstalled. Restart your PC to remove the remaining files."
+          : "Uninstalled.",
+      }));
 
       await refreshInstallStatus();
 
@@ -2780,6 +2863,22 @@ const AppContent = () => {
 
     }
 
+    const dirCheck = await invoke<{ empty: boolean; owner: string | null; conflict: boolean }>(
+      "check_install_dir",
+      { appId: app.id, installDir, exeName: app.exeName }
+    );
+    const overwrite =
+      dirCheck.conflict &&
+      window.confirm(
+        dirCheck.owner
+          ? `${installDir} already contains ${dirCheck.owner}. Install ${app.name} there anyway?`
+          : `${installDir} is not empty. Install ${app.name} there anyway?`
+      );
+    if (dirCheck.conflict && !overwrite) {
+      setInstallMessage((prev) => ({ ...prev, [app.id]: "Install folder is not empty." }));
+      return;
+    }
+
     setStoredInstallDir(app.id, installDir);
 
     setInstalling((prev) => ({ ...prev, [app.id]: true }));
@@ -2790,20 +2889,21 @@ const AppContent = () => {
 
     try {
 
-      const baseDir = await appDataDir();
-      const installersDir = await join(baseDir, "Enderfall", "Installers");
-      const installerPath = await invoke<string>("download_installer", {
-        appId: app.id,
-        url: releaseInfo.installerUrl,
-        destinationDir: installersDir,
-      });
-      setInstallMessage((prev) => ({ ...prev, [app.id]: "Installing..." }));
-
       const installerType = releaseInfo.installerType ?? "msi";
 
       if (installerType === "exe") {
-        const args = buildInstallerArgs(app, installDir);
-        await invoke("run_installer", { path: installerPath, args });
+        const baseDir = await appDataDir();
+        const installersDir = await join(baseDir, "Enderfall", "Installers");
+        const installerPath = await invoke<string>("download_installer", {
+          appId: app.id,
+          url: releaseInfo.installerUrl,
+          destinationDir: installersDir,
+        });
+        setInstallMessage((prev) => ({ ...prev, [app.id]: "Installing..." }));
+        const args = await resolveExeInstallerArgs(app.id, installDir, () =>
+          buildInstallerArgs(app, installDir)
+        );
+        await invoke("run_installer", { path: installerPath, args, appId: app.id, installDir });
         const exePath = getResolvedInstallExePath(app, installDir);
         if (exePath) {
           await invoke("create_shortcuts", {
@@ -2811,25 +2911,21 @@ const AppContent = () => {
             appName: app.name,
             createDesktopShortcut: options.createDesktopShortcut,
             createStartMenuShortcut: options.createStartMenuShortcut,
+            installDir,
           });
         }
       } else {
-        await invoke("install_msi_payload", {
-
+        await invoke("download_and_install", {
           appId: app.id,
-
-          installerPath,
-
+          url: releaseInfo.installerUrl,
           installDir,
-
           exeName: app.exeName,
-
           appName: app.name,
-
+          keepInstaller: false,
           createDesktopShortcut: options.createDesktopShortcut,
-
           createStartMenuShortcut: options.createStartMenuShortcut,
-
+          overwrite,
+          minOsVersion: app.minOsVersion,
         });
       }
 
@@ -2900,6 +2996,7 @@ const AppContent = () => {
           !!installedVersion &&
           !!releaseInfo?.version &&
           !!releaseInfo?.installerUrl &&
+          !versionPins[app.id] &&
           compareVersions(releaseInfo.version, installedVersion) > 0;
         return {
           app,
@@ -2940,6 +3037,8 @@ const AppContent = () => {
       cachedAdmin,
 
       releaseInfoByApp,
+
+      versionPins,
     ]
 
   );
@@ -3646,6 +3745,45 @@ const AppContent = () => {
             }
             label="Minimize to tray"
           />
+          <Toggle
+            variant="checkbox"
+            checked={Boolean(hubPreferences.notificationsEnabled)}
+            onChange={(event) =>
+              event.target.checked
+                ? void enableNotifications()
+                : void updateHubPreferences({ notificationsEnabled: false })
+            }
+            label="Desktop notifications"
+          />
+          <Toggle
+            variant="checkbox"
+            checked={Boolean(hubPreferences.scanDownloads)}
+            onChange={(event) =>
+              void updateHubPreferences({ scanDownloads: event.target.checked })
+            }
+            label="Scan installers before running them"
+          />
+          <Toggle
+            variant="checkbox"
+            checked={Boolean(hubPreferences.telemetryEnabled)}
+            onChange={(event) =>
+              void updateHubPreferences({ telemetryEnabled: event.target.checked })
+            }
+            label="Send anonymous failure reports"
+          />
+          <label>
+            Failure report endpoint
+            <Input
+              type="url"
+              value={telemetryEndpointDraft}
+              disabled={!hubPreferences.telemetryEnabled}
+              onChange={(event) => setTelemetryEndpointDraft(event.target.value)}
+              onBlur={() =>
+                void updateHubPreferences({ telemetryEndpoint: telemetryEndpointDraft })
+              }
+              placeholder="https://reports.example.com/hub"
+            />
+          </label>
         </div>
       </PreferencesModal>
 